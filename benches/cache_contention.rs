@@ -0,0 +1,153 @@
+//! Compares the release cache with the `Mutex<TimedCache>` it replaced, run with
+//! `cargo bench --bench cache_contention`.
+//!
+//! Reader threads, like the actix workers, look up the cached game release while another
+//! thread keeps missing the updater release, whose fetch from GitHub used to hold the
+//! `Mutex` of every lookup.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cached::{Cached, CachedAsync, TimedCache};
+use futures::executor::block_on;
+use moka::future::Cache;
+
+const READERS: usize = 4;
+const MEASUREMENT: Duration = Duration::from_secs(2);
+/// Duration of a GitHub fetch, shortened to keep the run brief.
+const FETCH_DURATION: Duration = Duration::from_millis(5);
+const LIFESPAN: Duration = Duration::from_secs(300);
+
+const GAME_RELEASE: &str = "latest_game_release";
+const UPDATER_RELEASE: &str = "latest_updater_release";
+
+type Release = Arc<String>;
+
+fn fetch(slow: bool) -> Release {
+    if slow {
+        thread::sleep(FETCH_DURATION);
+    }
+    Arc::new("0.5.1".to_string())
+}
+
+/// Cache before moka: the lock is held while the missing release is fetched.
+fn mutex_timed_cache() -> (impl Fn() + Sync, impl Fn() + Sync) {
+    let cache = Arc::new(Mutex::new(TimedCache::with_lifespan(LIFESPAN.as_secs())));
+
+    let lookup = {
+        let cache = cache.clone();
+        move || {
+            let mut cache = cache.lock().unwrap();
+            let release = block_on(
+                cache.try_get_or_set_with(GAME_RELEASE, || async { Ok::<_, ()>(fetch(false)) }),
+            );
+            assert!(release.is_ok());
+        }
+    };
+    let miss = move || {
+        let mut cache = cache.lock().unwrap();
+        cache.cache_remove(&UPDATER_RELEASE);
+        let release = block_on(
+            cache.try_get_or_set_with(UPDATER_RELEASE, || async { Ok::<_, ()>(fetch(true)) }),
+        );
+        assert!(release.is_ok());
+    };
+
+    (lookup, miss)
+}
+
+/// Current cache: a missing release is fetched without blocking the other lookups.
+fn moka_cache() -> (impl Fn() + Sync, impl Fn() + Sync) {
+    let cache: Cache<String, Release> = Cache::builder().time_to_live(LIFESPAN).build();
+
+    let lookup = {
+        let cache = cache.clone();
+        move || {
+            let release = block_on(cache.try_get_with(GAME_RELEASE.to_string(), async {
+                Ok::<_, ()>(fetch(false))
+            }));
+            assert!(release.is_ok());
+        }
+    };
+    let miss = move || {
+        block_on(async {
+            cache.invalidate(UPDATER_RELEASE).await;
+            let release = cache
+                .try_get_with(UPDATER_RELEASE.to_string(), async {
+                    Ok::<_, ()>(fetch(true))
+                })
+                .await;
+            assert!(release.is_ok());
+        })
+    };
+
+    (lookup, miss)
+}
+
+fn run(name: &str, (lookup, miss): (impl Fn() + Sync, impl Fn() + Sync)) {
+    // the game release is cached before measuring, as the refresh job keeps it warm
+    lookup();
+
+    let done = AtomicBool::new(false);
+    let started = Barrier::new(READERS + 1);
+    let (mut lookups, mut fetches): (Vec<_>, _) = thread::scope(|scope| {
+        let fetcher = scope.spawn(|| {
+            started.wait();
+            let mut latencies = Vec::new();
+            while !done.load(Ordering::Relaxed) {
+                let start = Instant::now();
+                miss();
+                latencies.push(start.elapsed());
+            }
+            latencies
+        });
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| {
+                scope.spawn(|| {
+                    started.wait();
+                    let start = Instant::now();
+                    let mut latencies = Vec::new();
+                    while start.elapsed() < MEASUREMENT {
+                        let start = Instant::now();
+                        lookup();
+                        latencies.push(start.elapsed());
+                    }
+                    latencies
+                })
+            })
+            .collect();
+
+        let lookups = readers
+            .into_iter()
+            .flat_map(|reader| reader.join().unwrap())
+            .collect();
+        done.store(true, Ordering::Relaxed);
+        (lookups, fetcher.join().unwrap())
+    });
+
+    println!("{name}");
+    report("lookups", &mut lookups);
+    report("fetches", &mut fetches);
+}
+
+fn report(name: &str, latencies: &mut [Duration]) {
+    latencies.sort_unstable();
+    let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+    println!(
+        "  {name}: {:>10.0}/s   p50 {:>12?}   p99 {:>12?}   max {:>12?}",
+        latencies.len() as f64 / MEASUREMENT.as_secs_f64(),
+        percentile(50),
+        percentile(99),
+        latencies[latencies.len() - 1],
+    );
+}
+
+fn main() {
+    println!(
+        "{READERS} readers for {MEASUREMENT:?}, while a release fetch of {FETCH_DURATION:?} keeps missing"
+    );
+    run("Mutex<TimedCache>", mutex_timed_cache());
+    run("moka::future::Cache", moka_cache());
+}
@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+
+/// Flavor of the assets whose pattern doesn't capture one.
+pub const DEFAULT_FLAVOR: &str = "release";
+
+/// Matches `<platform>[_releasedbg].<extension>`, such as `linux_x86_64_releasedbg.tar.gz`.
+pub const DEFAULT_PATTERN: &str = r"^(?P<platform>[^.]+?)(?:_(?P<flavor>releasedbg))?(?:\..*)?$";
+
+/// Reads the platform and flavor of release assets from their filename, with the first
+/// of the configured patterns matching it.
+pub struct AssetNameParser {
+    patterns: Vec<Regex>,
+}
+
+pub struct AssetName<'a> {
+    /// The `platform` capture, joined with the `arch` capture when the pattern has one.
+    pub name: Cow<'a, str>,
+    pub flavor: &'a str,
+}
+
+impl AssetNameParser {
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    pub fn parse<'a>(&self, filename: &'a str) -> Option<AssetName<'a>> {
+        self.patterns.iter().find_map(|pattern| {
+            let captures = pattern.captures(filename)?;
+            let platform = captures.name("platform")?.as_str();
+
+            Some(AssetName {
+                name: match captures.name("arch") {
+                    Some(arch) => Cow::Owned(format!("{platform}_{}", arch.as_str())),
+                    None => Cow::Borrowed(platform),
+                },
+                flavor: captures
+                    .name("flavor")
+                    .map_or(DEFAULT_FLAVOR, |flavor| flavor.as_str()),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data::Platform;
+
+    fn default_parser() -> AssetNameParser {
+        AssetNameParser::new(&[DEFAULT_PATTERN.to_string()]).unwrap()
+    }
+
+    #[test]
+    fn platform_extraction() {
+        let parser = default_parser();
+        let cases = [
+            ("windows_x64.zip", Some(Platform::WindowsX64)),
+            ("windows_x64_releasedbg.zip", Some(Platform::WindowsX64)),
+            ("macos_universal.tar.gz", Some(Platform::MacosUniversal)),
+            (
+                "linux_aarch64_releasedbg.tar.gz",
+                Some(Platform::LinuxAarch64),
+            ),
+            ("ゲーム.zip", None),
+            ("linux_x86_64_debug.tar.gz", None),
+            ("Linux_x86_64.tar.gz", None),
+        ];
+
+        for (filename, platform) in cases {
+            let asset_name = parser.parse(filename).unwrap();
+            assert_eq!(
+                asset_name.name.parse::<Platform>().ok(),
+                platform,
+                "{filename}"
+            );
+        }
+    }
+
+    /// The suffix stripping the default pattern replaced.
+    fn remove_game_suffix(asset_name: &str) -> &str {
+        let platform = asset_name
+            .find('.')
+            .map_or(asset_name, |pos| &asset_name[..pos]);
+        platform
+            .find("_releasedbg")
+            .map_or(platform, |pos| &platform[..pos])
+    }
+
+    #[test]
+    fn default_pattern_matches_previous_behavior() {
+        let parser = default_parser();
+        let cases = [
+            ("windows_x64.zip", "release"),
+            ("windows_x64_releasedbg.zip", "releasedbg"),
+            ("linux_x86_64.tar.gz", "release"),
+            ("linux_x86_64_releasedbg.tar.gz", "releasedbg"),
+            ("macos_universal.dmg", "release"),
+            ("macos_arm64_releasedbg.tar.gz", "releasedbg"),
+            ("assets.zip", "release"),
+            ("assets_releasedbg.zip", "releasedbg"),
+            ("windows_x64_this_updater_of_mine.zip", "release"),
+            ("linux_x86_64", "release"),
+            ("linux_x86_64_releasedbg", "releasedbg"),
+        ];
+
+        for (filename, flavor) in cases {
+            let asset_name = parser.parse(filename).unwrap();
+            assert_eq!(asset_name.name, remove_game_suffix(filename), "{filename}");
+            assert_eq!(asset_name.flavor, flavor, "{filename}");
+        }
+    }
+
+    #[test]
+    fn arch_capture() {
+        let parser = AssetNameParser::new(&[
+            r"^tsom-(?P<platform>[a-z]+)-(?P<arch>[a-z0-9_]+)(?:-(?P<flavor>debug))?\.".to_string(),
+            DEFAULT_PATTERN.to_string(),
+        ])
+        .unwrap();
+        let cases = [
+            ("tsom-linux-x86_64.tar.gz", "linux_x86_64", "release"),
+            ("tsom-windows-arm64-debug.zip", "windows_arm64", "debug"),
+            // falls back to the next pattern
+            (
+                "macos_universal_releasedbg.zip",
+                "macos_universal",
+                "releasedbg",
+            ),
+        ];
+
+        for (filename, name, flavor) in cases {
+            let asset_name = parser.parse(filename).unwrap();
+            assert_eq!(asset_name.name, name, "{filename}");
+            assert_eq!(asset_name.flavor, flavor, "{filename}");
+        }
+    }
+
+    #[test]
+    fn no_match() {
+        let parser = default_parser();
+        // nothing before the extension
+        assert!(parser.parse(".zip").is_none());
+        assert!(parser.parse("").is_none());
+
+        let parser =
+            AssetNameParser::new(&[r"^tsom-(?P<platform>[a-z_0-9]+)\.zip$".to_string()]).unwrap();
+        for filename in ["windows_x64.zip", "tsom-windows_x64.tar.gz", "tsom-.zip"] {
+            assert!(parser.parse(filename).is_none(), "{filename}");
+        }
+    }
+}
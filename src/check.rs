@@ -0,0 +1,110 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::asset_names::DEFAULT_FLAVOR;
+use crate::config::{self, CONFIG_PATH};
+use crate::fetcher::Fetcher;
+use crate::shared_cache::SharedCache;
+
+#[derive(Default)]
+struct Report {
+    failed: bool,
+}
+
+impl Report {
+    fn ok(&self, check: &str, detail: impl Display) {
+        println!("[ok]     {check}: {detail}");
+    }
+
+    fn fail(&mut self, check: &str, detail: impl Display) {
+        println!("[failed] {check}: {detail}");
+        self.failed = true;
+    }
+}
+
+/// Checks that the API can start and serve releases with the current configuration,
+/// returns whether every check passed.
+pub async fn run() -> bool {
+    let mut report = Report::default();
+
+    // confy would write a default configuration, which would hide a missing mount
+    if !Path::new(CONFIG_PATH).exists() {
+        report.fail("config", format!("{CONFIG_PATH} not found"));
+        return false;
+    }
+
+    let mut config = match config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            report.fail("config", err);
+            return false;
+        }
+    };
+
+    let mut errors = config.load_secrets();
+    errors.extend(config.validate());
+    if errors.is_empty() {
+        report.ok("config", format!("{CONFIG_PATH} is valid"));
+    }
+    for error in errors {
+        report.fail("config", error);
+    }
+
+    if !config.tenants.is_empty() {
+        match config.load_tenants() {
+            Ok(tenants) => report.ok(
+                "tenants",
+                format!("{} configurations are valid", tenants.len()),
+            ),
+            Err(errors) => {
+                for error in errors {
+                    report.fail("tenants", error);
+                }
+            }
+        }
+    }
+
+    if let Some(redis_url) = &config.cache.redis_url {
+        match SharedCache::connect(
+            redis_url,
+            &config.cache.redis_key_prefix,
+            config.cache.lifespan,
+            config.cache.updater_lifespan,
+        )
+        .await
+        {
+            Ok(_) => report.ok("redis", "connected"),
+            Err(err) => report.fail("redis", err),
+        }
+    }
+
+    let fetcher = match Fetcher::from_config(&config) {
+        Ok(fetcher) => fetcher,
+        Err(err) => {
+            report.fail("github", err);
+            return false;
+        }
+    };
+
+    match fetcher.get_latest_updater_release().await {
+        Ok(release) => report.ok("updater release", format!("{} platforms", release.len())),
+        Err(err) => report.fail("updater release", err),
+    }
+
+    match fetcher
+        .get_latest_game_release(config.game.pinned_version.as_ref(), DEFAULT_FLAVOR)
+        .await
+    {
+        Ok(release) => report.ok(
+            "game release",
+            format!(
+                "version {} with {} platforms",
+                release.version,
+                release.binaries.len()
+            ),
+        ),
+        Err(err) => report.fail("game release", err),
+    }
+
+    !report.failed
+}
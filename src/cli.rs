@@ -0,0 +1,32 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about = "API of ThisSpaceOfMine")]
+pub struct Cli {
+    /// Shorthand for the check command
+    #[arg(long)]
+    check: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, Subcommand)]
+pub enum Command {
+    /// Run the API server (default)
+    Run,
+    /// Check the configuration, Redis and GitHub access, then exit
+    Check,
+    /// Generate a random token to use as the security.admin_token of the configuration
+    CreateAdminToken,
+}
+
+impl Cli {
+    pub fn command(&self) -> Command {
+        match self.command {
+            Some(command) => command,
+            None if self.check => Command::Check,
+            None => Command::Run,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::AppData;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    /// Responses smaller than this many bytes are sent as is.
+    pub min_size: u64,
+    /// Media types eligible for compression, without their parameters.
+    pub content_types: Vec<String>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size: 1024,
+            content_types: vec![
+                "application/json".to_string(),
+                "application/atom+xml".to_string(),
+                "application/msgpack".to_string(),
+            ],
+        }
+    }
+}
+
+impl CompressionConfig {
+    fn should_compress(&self, response: &ServiceResponse<impl MessageBody>) -> bool {
+        // streamed bodies have no known size and are usually large
+        if let BodySize::Sized(size) = response.response().body().size() {
+            if size < self.min_size {
+                return false;
+            }
+        }
+
+        let Some(content_type) = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return false;
+        };
+
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        self.content_types
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(media_type))
+    }
+}
+
+/// Marks the responses that shouldn't be compressed with `Content-Encoding: identity`,
+/// which actix's `Compress` middleware leaves untouched; it has to be wrapped inside it.
+pub async fn filter_responses(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let app_data = req
+        .app_data::<web::Data<AppData>>()
+        .cloned()
+        .expect("missing app data");
+
+    let mut response = next.call(req).await?;
+    if !response.headers().contains_key(header::CONTENT_ENCODING)
+        && !app_data.config.compression.should_compress(&response)
+    {
+        response.headers_mut().insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static("identity"),
+        );
+    }
+
+    Ok(response)
+}
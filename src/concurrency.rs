@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::{header, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::timeouts::RouteGroup;
+use crate::{envelope, AppData};
+
+/// Seconds a client is asked to wait when its route group is saturated.
+const RETRY_AFTER: u64 = 1;
+
+/// Requests handled at once by each route group, 0 disables the limit.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConcurrencyLimits {
+    /// /game_version and /updater_version.
+    pub version: usize,
+    /// /v2 routes, except the feeds, admin and telemetry ones which keep their group.
+    pub v2: usize,
+    pub feeds: usize,
+    pub admin: usize,
+    pub telemetry: usize,
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            v2: 0,
+            feeds: 0,
+            // validating the releases goes through every one of them
+            admin: 4,
+            telemetry: 64,
+        }
+    }
+}
+
+impl ConcurrencyLimits {
+    fn limit(&self, group: RouteGroup) -> usize {
+        match group {
+            RouteGroup::Version => self.version,
+            RouteGroup::V2 => self.v2,
+            RouteGroup::Feeds => self.feeds,
+            RouteGroup::Admin => self.admin,
+            RouteGroup::Telemetry => self.telemetry,
+        }
+    }
+}
+
+/// Requests being handled by each route group, shared by every worker.
+#[derive(Default)]
+pub struct InFlight([AtomicUsize; RouteGroup::COUNT]);
+
+/// Releases its slot when the request is done, even when the handler is dropped.
+struct Slot<'a>(&'a AtomicUsize);
+
+impl InFlight {
+    fn acquire(&self, group: RouteGroup, limit: usize) -> Option<Slot<'_>> {
+        let count = &self.0[group as usize];
+        count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < limit).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Slot(count))
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Answers with a 503 when a route group already handles as many requests as it allows,
+/// so a burst on the slow routes can't starve the others.
+pub async fn limit_concurrency(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let app_data = req
+        .app_data::<web::Data<AppData>>()
+        .cloned()
+        .expect("missing app data");
+
+    let group = RouteGroup::of(req.path(), &app_data.config.tenants);
+    let limit = app_data.config.concurrency_limits.limit(group);
+    if limit == 0 {
+        return next.call(req).await;
+    }
+
+    let Some(_slot) = app_data.in_flight.acquire(group, limit) else {
+        log::warn!(
+            "{} {} rejected, too many requests in flight",
+            req.method(),
+            req.path()
+        );
+
+        let mut response = match group {
+            // the legacy routes have no error body
+            RouteGroup::Version => HttpResponse::ServiceUnavailable().finish(),
+            _ => envelope::error(
+                req.request(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "overloaded",
+                "too many requests are being handled, retry later",
+                &[],
+            ),
+        };
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, RETRY_AFTER.into());
+        return Err(InternalError::from_response("too many requests in flight", response).into());
+    };
+
+    next.call(req).await
+}
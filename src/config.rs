@@ -1,29 +1,562 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::iter;
+use std::net::IpAddr;
+use std::path::Path;
+
+use regex::Regex;
 use secure_string::SecureString;
+use semver::Version;
 use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::asset_names;
+use crate::compression::CompressionConfig;
+use crate::concurrency::ConcurrencyLimits;
+use crate::diagnostics::AssetChecks;
+use crate::fetcher::PackConfig;
+use crate::game_data::Platform;
+use crate::logging::LogFormat;
+use crate::payload_logging::PayloadLogging;
+use crate::routes;
+use crate::scheduler::JobConfig;
+use crate::secrets::{SecretProvider, SecretProviderKind};
+use crate::timeouts::RequestTimeouts;
+use crate::webhooks::WebhookConfig;
+
+pub const CONFIG_PATH: &str = "tsom_api_config.toml";
 
+/// Top-level keys are only tables, unknown keys are rejected so a typo or a
+/// configuration written for an older version doesn't silently fall back to defaults.
 #[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ApiConfig {
+    pub server: ServerConfig,
+    pub github: GithubConfig,
+    /// Release source fetched along `github` by the cache refresh job, whose differences
+    /// are logged while `github` keeps being served, to validate a source before switching.
+    pub shadow_github: Option<GithubConfig>,
+    pub game: GameConfig,
+    pub updater: UpdaterConfig,
+    pub cache: CacheConfig,
+    pub security: SecurityConfig,
+    pub telemetry: TelemetryConfig,
+    pub compression: CompressionConfig,
+    pub request_timeouts: RequestTimeouts,
+    pub concurrency_limits: ConcurrencyLimits,
+    pub payload_logging: PayloadLogging,
+    /// Files translating the /v2 error descriptions, by language.
+    pub error_catalogs: BTreeMap<String, String>,
+    pub asset_checks: AssetChecks,
+    pub cache_refresh_job: JobConfig,
+    pub updater_refresh_job: JobConfig,
+    pub download_stats_job: JobConfig,
+    pub webhooks: Vec<WebhookConfig>,
+    pub packs: Vec<PackConfig>,
+    /// Other games served by this instance under a path prefix, by prefix. Each one has
+    /// its own configuration file, in which the [server], [compression],
+    /// [request_timeouts], [concurrency_limits], [payload_logging] and [tenants] tables
+    /// are ignored.
+    pub tenants: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
     pub listen_address: String,
     pub listen_port: u16,
+    pub log_format: LogFormat,
+    pub sentry_dsn: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GithubConfig {
     pub repo_owner: String,
     pub game_repository: String,
     pub updater_repository: String,
     pub updater_filename: String,
-    pub cache_lifespan: u64,
-    pub github_pat: Option<SecureString>,
+    /// Stripped from release tags before parsing them as versions, such as "v".
+    pub tag_prefix: String,
+    /// Warns about the releases skipped because of their tag.
+    pub strict_tags: bool,
+    /// Regexes reading the platform, and optionally the arch and flavor, of release
+    /// assets from their filename, tried in order.
+    pub asset_patterns: Vec<String>,
+    pub pat: Option<SecureString>,
+    /// Reference to the pat read with `security.secret_provider`, instead of `pat`.
+    pub pat_file: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    /// Flavors which can be requested from /game_version, as captured by the patterns.
+    pub flavors: Vec<String>,
+    /// Game version served instead of the latest one, can be overridden from the admin API.
+    pub pinned_version: Option<Version>,
+    /// Serves the platforms without any stable build from their latest prerelease.
+    pub prerelease_backfill: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpdaterConfig {
+    /// Known-good updater version served for a platform when the latest updater release
+    /// lacks it or its checksum is broken, so an incomplete release can't brick launchers.
+    pub fallback_versions: HashMap<Platform, Version>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub lifespan: u64,
+    /// Lifespan of the updater release, which changes far less often than the game.
+    pub updater_lifespan: u64,
+    pub redis_url: Option<String>,
+    pub redis_key_prefix: String,
+    pub download_stats_history: usize,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityConfig {
+    pub admin_token: Option<SecureString>,
+    pub admin_token_file: Option<String>,
+    /// How the `*_file` keys are read.
+    pub secret_provider: SecretProviderKind,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub sink_url: Option<String>,
+    pub sample_rate: f64,
+    pub max_batch_size: usize,
+    /// Batches forwarded at once, the next ones are dropped until one is done.
+    pub max_pending_forwards: usize,
 }
 
 impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            github: GithubConfig::default(),
+            shadow_github: None,
+            game: GameConfig::default(),
+            updater: UpdaterConfig::default(),
+            cache: CacheConfig::default(),
+            security: SecurityConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            compression: CompressionConfig::default(),
+            request_timeouts: RequestTimeouts::default(),
+            concurrency_limits: ConcurrencyLimits::default(),
+            payload_logging: PayloadLogging::default(),
+            error_catalogs: BTreeMap::new(),
+            asset_checks: AssetChecks::default(),
+            cache_refresh_job: JobConfig {
+                interval: 4 * 60,
+                jitter: 15,
+            },
+            updater_refresh_job: JobConfig {
+                interval: 10 * 60,
+                jitter: 30,
+            },
+            download_stats_job: JobConfig {
+                interval: 60 * 60,
+                jitter: 60,
+            },
+            webhooks: Vec::new(),
+            packs: Vec::new(),
+            tenants: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             listen_address: "0.0.0.0".to_string(),
             listen_port: 14770,
+            log_format: LogFormat::Pretty,
+            sentry_dsn: None,
+        }
+    }
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
             repo_owner: "DigitalpulseSoftware".to_string(),
             game_repository: "ThisSpaceOfMine".to_string(),
-            updater_filename: "this_updater_of_mine".to_string(),
             updater_repository: "ThisUpdaterOfMine".to_string(),
-            cache_lifespan: 5 * 60,
-            github_pat: None,
+            updater_filename: "this_updater_of_mine".to_string(),
+            tag_prefix: String::new(),
+            strict_tags: false,
+            asset_patterns: vec![asset_names::DEFAULT_PATTERN.to_string()],
+            pat: None,
+            pat_file: None,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            flavors: vec![
+                asset_names::DEFAULT_FLAVOR.to_string(),
+                "releasedbg".to_string(),
+            ],
+            pinned_version: None,
+            prerelease_backfill: false,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            lifespan: 5 * 60,
+            updater_lifespan: 30 * 60,
+            redis_url: None,
+            redis_key_prefix: "tsom_api:".to_string(),
+            download_stats_history: 288,
+        }
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            sink_url: None,
+            sample_rate: 1.0,
+            max_batch_size: 50,
+            max_pending_forwards: 256,
+        }
+    }
+}
+
+/// Error of [`load`], confy hides the location of TOML errors behind its own message.
+#[derive(Debug)]
+pub struct LoadError(confy::ConfyError);
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        match self.0.source() {
+            Some(source) => write!(f, ": {source}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Loads the configuration from [`CONFIG_PATH`], writing the default one if it's missing.
+pub fn load() -> Result<ApiConfig, LoadError> {
+    confy::load_path(CONFIG_PATH).map_err(LoadError)
+}
+
+impl ApiConfig {
+    /// Replaces the `*_file` keys by the secrets they reference, returns a description of
+    /// every secret which couldn't be read.
+    pub fn load_secrets(&mut self) -> Vec<String> {
+        let provider = match self.security.secret_provider.provider() {
+            Ok(provider) => provider,
+            Err(err) => return vec![format!("security.secret_provider: {err}")],
+        };
+
+        let mut errors = Vec::new();
+        let mut load = |key: &str, file: &Option<String>, value: &mut Option<SecureString>| {
+            if let Err(err) = load_secret(provider.as_ref(), key, file.as_deref(), value) {
+                errors.push(err);
+            }
+        };
+
+        let github = &mut self.github;
+        load("github.pat", &github.pat_file, &mut github.pat);
+        if let Some(github) = &mut self.shadow_github {
+            load("shadow_github.pat", &github.pat_file, &mut github.pat);
         }
+        let security = &mut self.security;
+        load(
+            "security.admin_token",
+            &security.admin_token_file,
+            &mut security.admin_token,
+        );
+        for (i, webhook) in self.webhooks.iter_mut().enumerate() {
+            load(
+                &format!("webhooks[{i}].secret"),
+                &webhook.secret_file,
+                &mut webhook.secret,
+            );
+        }
+
+        errors
+    }
+
+    /// Loads and checks the configurations of the tenants, the errors are prefixed by the
+    /// path of their file. Should only be called on a valid configuration.
+    pub fn load_tenants(&self) -> Result<Vec<(String, ApiConfig)>, Vec<String>> {
+        let mut tenants = Vec::new();
+        let mut errors = Vec::new();
+
+        for (name, path) in &self.tenants {
+            let mut config: ApiConfig = match confy::load_path(path) {
+                Ok(config) => config,
+                Err(err) => {
+                    errors.push(format!("{path}: {}", LoadError(err)));
+                    continue;
+                }
+            };
+
+            let mut config_errors = config.load_secrets();
+            config_errors.extend(config.validate());
+            if !config.tenants.is_empty() {
+                config_errors.push("tenants can't have tenants".to_string());
+            }
+            errors.extend(config_errors.iter().map(|error| format!("{path}: {error}")));
+
+            tenants.push((name.clone(), config));
+        }
+
+        // the games would overwrite each other's releases
+        // the first one can't conflict, so it doesn't need a name
+        let games = iter::once(("", self))
+            .chain(tenants.iter().map(|(name, config)| (name.as_str(), config)));
+        let mut used_prefixes = Vec::new();
+        for (name, config) in games {
+            let Some(redis_url) = &config.cache.redis_url else {
+                continue;
+            };
+            let key = (redis_url, &config.cache.redis_key_prefix);
+            if used_prefixes.contains(&key) {
+                errors.push(format!(
+                    "tenants.{name}: cache.redis_key_prefix \"{}\" is already used by another game",
+                    config.cache.redis_key_prefix
+                ));
+            }
+            used_prefixes.push(key);
+        }
+
+        if errors.is_empty() {
+            Ok(tenants)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns a description of every invalid value prefixed by its TOML path, confy only
+    /// checks the types.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.server.listen_address.parse::<IpAddr>().is_err() {
+            errors.push(format!(
+                "server.listen_address \"{}\" is not an IP address",
+                self.server.listen_address
+            ));
+        }
+
+        validate_github("github", &self.github, &mut errors);
+        if let Some(github) = &self.shadow_github {
+            validate_github("shadow_github", github, &mut errors);
+        }
+
+        if !self
+            .game
+            .flavors
+            .iter()
+            .any(|flavor| flavor == asset_names::DEFAULT_FLAVOR)
+        {
+            errors.push(format!(
+                "game.flavors must contain \"{}\"",
+                asset_names::DEFAULT_FLAVOR
+            ));
+        }
+
+        if self.cache.lifespan == 0 {
+            errors.push("cache.lifespan must be greater than 0".to_string());
+        }
+        if self.cache.updater_lifespan == 0 {
+            errors.push("cache.updater_lifespan must be greater than 0".to_string());
+        }
+
+        if self.cache.download_stats_history == 0 {
+            errors.push("cache.download_stats_history must be greater than 0".to_string());
+        }
+
+        if !(0.0..=1.0).contains(&self.telemetry.sample_rate) {
+            errors.push("telemetry.sample_rate must be between 0 and 1".to_string());
+        }
+
+        if self.telemetry.max_batch_size == 0 {
+            errors.push("telemetry.max_batch_size must be greater than 0".to_string());
+        }
+
+        if self.telemetry.max_pending_forwards == 0 {
+            errors.push("telemetry.max_pending_forwards must be greater than 0".to_string());
+        }
+
+        if self.asset_checks.max_size <= 0 {
+            errors.push("asset_checks.max_size must be greater than 0".to_string());
+        }
+
+        for (i, pack) in self.packs.iter().enumerate() {
+            for (key, value) in [
+                ("name", &pack.name),
+                ("repository", &pack.repository),
+                ("asset_name", &pack.asset_name),
+            ] {
+                if value.is_empty() {
+                    errors.push(format!("packs[{i}].{key} must not be empty"));
+                }
+            }
+
+            if self.packs[..i].iter().any(|other| other.name == pack.name) {
+                errors.push(format!("packs[{i}].name \"{}\" is used twice", pack.name));
+            }
+        }
+
+        for (language, path) in &self.error_catalogs {
+            if !Path::new(path).is_file() {
+                errors.push(format!("error_catalogs.{language}: {path} not found"));
+            }
+        }
+
+        for (name, path) in &self.tenants {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                errors.push(format!("tenants.{name} is not a valid path prefix"));
+            } else if routes::ROOT_SEGMENTS.contains(&name.as_str()) {
+                errors.push(format!("tenants.{name} is used by the API routes"));
+            }
+
+            // confy would write a default configuration, serving the default game twice
+            if !Path::new(path).is_file() {
+                errors.push(format!("tenants.{name}: {path} not found"));
+            }
+        }
+
+        let urls = [
+            ("server.sentry_dsn", self.server.sentry_dsn.as_ref()),
+            ("cache.redis_url", self.cache.redis_url.as_ref()),
+            ("telemetry.sink_url", self.telemetry.sink_url.as_ref()),
+        ];
+        for (key, url) in urls {
+            if let Some(Err(err)) = url.map(|url| Url::parse(url)) {
+                errors.push(format!("{key} is not a valid url: {err}"));
+            }
+        }
+
+        for (i, webhook) in self.webhooks.iter().enumerate() {
+            if let Err(err) = Url::parse(&webhook.url) {
+                errors.push(format!("webhooks[{i}].url is not a valid url: {err}"));
+            }
+        }
+
+        errors
+    }
+}
+
+fn load_secret(
+    provider: &dyn SecretProvider,
+    key: &str,
+    file: Option<&str>,
+    value: &mut Option<SecureString>,
+) -> Result<(), String> {
+    let Some(file) = file else {
+        return Ok(());
+    };
+
+    if value.is_some() {
+        return Err(format!("{key} and {key}_file are both set"));
+    }
+
+    let secret = provider
+        .read(file)
+        .map_err(|err| format!("{key}_file \"{file}\": {err}"))?;
+    *value = Some(secret);
+    Ok(())
+}
+
+fn validate_github(table: &str, github: &GithubConfig, errors: &mut Vec<String>) {
+    for (key, value) in [
+        ("repo_owner", &github.repo_owner),
+        ("game_repository", &github.game_repository),
+        ("updater_repository", &github.updater_repository),
+        ("updater_filename", &github.updater_filename),
+    ] {
+        if value.is_empty() {
+            errors.push(format!("{table}.{key} must not be empty"));
+        }
+    }
+
+    if github.asset_patterns.is_empty() {
+        errors.push(format!("{table}.asset_patterns must not be empty"));
+    }
+    for (i, pattern) in github.asset_patterns.iter().enumerate() {
+        let key = format!("{table}.asset_patterns[{i}]");
+        match Regex::new(pattern) {
+            Ok(regex) if !regex.capture_names().any(|name| name == Some("platform")) => {
+                errors.push(format!("{key} has no platform capture"))
+            }
+            Ok(_) => {}
+            Err(err) => errors.push(format!("{key} is not a valid regex: {err}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_file_is_valid() {
+        let config: ApiConfig = toml::from_str(include_str!("../tsom_api_config.toml.default"))
+            .expect("the default configuration should parse");
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let tables = [
+            "[server]",
+            "[github]",
+            "[shadow_github]",
+            "[game]",
+            "[updater]",
+            "[cache]",
+            "[security]",
+            "[telemetry]",
+            "[compression]",
+            "[request_timeouts]",
+            "[concurrency_limits]",
+            "[payload_logging]",
+            "[asset_checks]",
+            "[cache_refresh_job]\ninterval = 60\njitter = 0",
+            "[updater_refresh_job]\ninterval = 60\njitter = 0",
+            "[download_stats_job]\ninterval = 60\njitter = 0",
+            "[[webhooks]]\nurl = \"https://example.com/hook\"",
+            "[[packs]]\nname = \"music\"\nrepository = \"music\"\nasset_name = \"music\"",
+        ];
+
+        for table in tables {
+            assert!(
+                toml::from_str::<ApiConfig>(table).is_ok(),
+                "{table} should parse"
+            );
+            let typo = format!("{table}\nunknown_key = 1");
+            assert!(
+                toml::from_str::<ApiConfig>(&typo).is_err(),
+                "{table} should reject unknown keys"
+            );
+        }
+
+        assert!(toml::from_str::<ApiConfig>("unknown_key = 1").is_err());
     }
 }
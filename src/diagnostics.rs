@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use octocrab::models::repos;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_names::AssetNameParser;
+use crate::fetcher::parse_tag;
+use crate::game_data::Platform;
+
+/// Sanity checks of the release assets, the suspicious ones are only reported unless
+/// `strict` is set, in which case they are not served either.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetChecks {
+    pub strict: bool,
+    /// In bytes.
+    pub max_size: i64,
+    /// Content types set by the uploader which are expected, any is accepted when empty.
+    pub content_types: Vec<String>,
+}
+
+impl Default for AssetChecks {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            max_size: 8 * 1024 * 1024 * 1024,
+            content_types: [
+                "application/gzip",
+                "application/octet-stream",
+                "application/x-gtar",
+                "application/x-gzip",
+                "application/x-tar",
+                "application/x-zip-compressed",
+                "application/zip",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+impl AssetChecks {
+    /// Describes what's suspicious about the asset, if anything.
+    pub fn issues(&self, asset: &repos::Asset) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        if asset.size <= 0 {
+            issues.push(format!("asset {} is empty", asset.name));
+        } else if asset.size > self.max_size {
+            issues.push(format!(
+                "asset {} is {} bytes, more than the {} bytes allowed",
+                asset.name, asset.size, self.max_size
+            ));
+        }
+
+        if !self.content_types.is_empty()
+            && !self
+                .content_types
+                .iter()
+                .any(|content_type| content_type.eq_ignore_ascii_case(&asset.content_type))
+        {
+            issues.push(format!(
+                "asset {} has an unexpected content type \"{}\"",
+                asset.name, asset.content_type
+            ));
+        }
+
+        issues
+    }
+}
+
+/// Packaging mistakes found in the releases, served by `/admin/releases/validate`.
+#[derive(Serialize)]
+pub struct ValidationReport {
+    pub game: Vec<ReleaseReport>,
+    pub updater: Option<ReleaseReport>,
+}
+
+#[derive(Serialize)]
+pub struct ReleaseReport {
+    pub tag: String,
+    pub prerelease: bool,
+    pub issues: Vec<String>,
+}
+
+/// Reports the unparsable tag, the assets without checksum and the assets whose name
+/// doesn't resolve to a platform once stripped of its suffix, or to one of `extra_names`.
+pub fn inspect_release(
+    release: &repos::Release,
+    tag_prefix: &str,
+    asset_names: &AssetNameParser,
+    asset_checks: &AssetChecks,
+    suffix: &str,
+    extra_names: &[&str],
+) -> ReleaseReport {
+    let mut issues = Vec::new();
+
+    if let Err(err) = parse_tag(&release.tag_name, tag_prefix) {
+        issues.push(format!(
+            "tag \"{}\" is not a semver version, the release is ignored: {err}",
+            release.tag_name
+        ));
+    }
+
+    let names = release
+        .assets
+        .iter()
+        .map(|asset| asset.name.as_str())
+        .collect::<HashSet<_>>();
+
+    for asset in &release.assets {
+        if let Some(target) = asset.name.strip_suffix(".sha256") {
+            if !names.contains(target) {
+                issues.push(format!("checksum {} has no matching asset", asset.name));
+            }
+            continue;
+        }
+
+        issues.extend(asset_checks.issues(asset));
+
+        if !names.contains(format!("{}.sha256", asset.name).as_str()) {
+            issues.push(format!("asset {} has no checksum", asset.name));
+        }
+
+        let Some(asset_name) = asset_names.parse(&asset.name) else {
+            issues.push(format!("asset {} matches no asset pattern", asset.name));
+            continue;
+        };
+
+        let name = strip_suffix(&asset_name.name, suffix);
+        if !extra_names.contains(&name) && name.parse::<Platform>().is_err() {
+            issues.push(format!(
+                "asset {} is read as \"{name}\", which isn't a known platform",
+                asset.name
+            ));
+        }
+    }
+
+    ReleaseReport {
+        tag: release.tag_name.clone(),
+        prerelease: release.prerelease,
+        issues,
+    }
+}
+
+pub fn release_platforms(
+    release: &repos::Release,
+    asset_names: &AssetNameParser,
+    suffix: &str,
+) -> HashSet<Platform> {
+    release
+        .assets
+        .iter()
+        .filter(|asset| !asset.name.ends_with(".sha256"))
+        .filter_map(|asset| asset_names.parse(&asset.name))
+        .filter_map(|asset_name| strip_suffix(&asset_name.name, suffix).parse().ok())
+        .collect()
+}
+
+fn strip_suffix<'a>(name: &'a str, suffix: &str) -> &'a str {
+    name.strip_suffix(suffix).unwrap_or(name)
+}
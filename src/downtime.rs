@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+const MESSAGE_MAX_LEN: usize = 500;
+
+/// Maintenance window scheduled from the admin API, announced to the launchers through
+/// /game_version so they can warn the players beforehand.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Downtime {
+    /// When launchers start being told about it, right away when unset.
+    pub announced_at: Option<DateTime<Utc>>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub message: Option<String>,
+}
+
+/// Downtime as announced in /game_version.
+#[derive(Clone, Serialize)]
+pub struct DowntimeNotice {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl Downtime {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ends_at <= self.starts_at {
+            return Err("ends_at must be after starts_at".to_string());
+        }
+        if self
+            .announced_at
+            .is_some_and(|announced_at| announced_at > self.starts_at)
+        {
+            return Err("announced_at must not be after starts_at".to_string());
+        }
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|message| message.chars().count() > MESSAGE_MAX_LEN)
+        {
+            return Err(format!(
+                "message must not be longer than {MESSAGE_MAX_LEN} characters"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The notice is served from the announcement until the end of the downtime.
+    pub fn notice(&self, now: DateTime<Utc>) -> Option<DowntimeNotice> {
+        let announced = self
+            .announced_at
+            .is_none_or(|announced_at| announced_at <= now);
+        (announced && now < self.ends_at).then(|| DowntimeNotice {
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            message: self.message.clone(),
+        })
+    }
+}
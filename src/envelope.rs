@@ -0,0 +1,166 @@
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{error, web, Error, HttpMessage, HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use crate::logging::RequestId;
+use crate::negotiation::{Format, MSGPACK_CONTENT_TYPE};
+use crate::{i18n, negotiation, AppData};
+
+/// Body of every successful `/v2` response.
+#[derive(Serialize)]
+struct DataEnvelope<T> {
+    data: T,
+}
+
+/// Body of every failed `/v2` response.
+#[derive(Serialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    description: String,
+    request_id: Option<String>,
+}
+
+pub fn data<T: Serialize>(req: &HttpRequest, data: T) -> HttpResponse {
+    negotiation::respond(req, HttpResponse::Ok(), &DataEnvelope { data })
+}
+
+/// Builds an error response, the code is stable and meant for clients while the
+/// description is meant for humans. The description is translated in the language asked
+/// with `Accept-Language` when a catalog has it, its `{name}` placeholders are replaced
+/// by the matching args.
+pub fn error(
+    req: &HttpRequest,
+    status: StatusCode,
+    code: &'static str,
+    description: &str,
+    args: &[(&str, &str)],
+) -> HttpResponse {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|request_id| request_id.0.clone());
+
+    let app_data = req.app_data::<web::Data<AppData>>();
+    let translation = app_data.and_then(|app_data| app_data.catalogs.lookup(req, code));
+
+    let mut response = HttpResponse::build(status);
+    let description = match translation {
+        Some((language, translated)) => {
+            response.insert_header((header::CONTENT_LANGUAGE, language));
+            i18n::format(translated, args)
+        }
+        None => i18n::format(description, args),
+    };
+
+    let body = ErrorEnvelope {
+        error: ErrorBody {
+            code,
+            description,
+            request_id,
+        },
+    };
+    negotiation::respond(req, response, &body)
+}
+
+/// Wraps the responses of the routes shared with the unversioned API, which are written
+/// without the envelope. JSON and MessagePack bodies become its data while the others,
+/// such as the Atom feed, are left as they are. Every error gets an error body, with a
+/// code named after its status and the plain text of the error as its description.
+pub async fn wrap_responses(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    // the middlewares of the wrapped routes answer their errors for them to be wrapped,
+    // the request is gone along with the ones they return
+    let (req, res) = next.call(req).await?.into_parts();
+    let res = res.map_into_boxed_body();
+
+    let status = res.status();
+    let res = match (
+        status.is_client_error() || status.is_server_error(),
+        body_format(&res),
+    ) {
+        (true, format) => wrap_error(&req, res, format).await?,
+        (false, Some(format)) => wrap_data(res, format).await?,
+        (false, None) => res,
+    };
+    Ok(ServiceResponse::new(req, res))
+}
+
+async fn wrap_error(
+    req: &HttpRequest,
+    res: HttpResponse,
+    format: Option<Format>,
+) -> Result<HttpResponse, Error> {
+    let status = res.status();
+    let (res, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let description = match std::str::from_utf8(&bytes) {
+        Ok(text) if !text.is_empty() && format.is_none() => text.to_string(),
+        _ => status.canonical_reason().unwrap_or("error").to_lowercase(),
+    };
+    let mut wrapped = self::error(req, status, error_code(status), &description, &[]);
+    // such as Retry-After
+    for name in res.headers().keys() {
+        if !wrapped.headers().contains_key(name) {
+            for value in res.headers().get_all(name) {
+                wrapped.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+    }
+    Ok(wrapped)
+}
+
+async fn wrap_data(res: HttpResponse, format: Format) -> Result<HttpResponse, Error> {
+    let (res, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let data: Option<serde_json::Value> = match format {
+        Format::Json => serde_json::from_slice(&bytes).ok(),
+        Format::MessagePack => rmp_serde::from_slice(&bytes).ok(),
+    };
+    let body = data
+        .and_then(|data| negotiation::encode(format, &DataEnvelope { data }))
+        .unwrap_or(bytes);
+    Ok(res.set_body(BoxBody::new(body)))
+}
+
+/// Format of a response body, None when it isn't serialized data.
+fn body_format(res: &HttpResponse) -> Option<Format> {
+    let content_type = res.headers().get(header::CONTENT_TYPE)?.to_str().ok()?;
+    match content_type.split(';').next()?.trim() {
+        "application/json" => Some(Format::Json),
+        MSGPACK_CONTENT_TYPE => Some(Format::MessagePack),
+        _ => None,
+    }
+}
+
+fn error_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limited",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        StatusCode::GATEWAY_TIMEOUT => "timeout",
+        status if status.is_client_error() => "invalid_request",
+        _ => "internal_error",
+    }
+}
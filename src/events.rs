@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const NAME_MAX_LEN: usize = 64;
+
+/// Limited-time content enabled by the game between two dates. The dates must carry
+/// their offset and are stored in UTC, so the game doesn't depend on the server timezone.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Free-form content for the game, such as the items the event unlocks.
+    #[serde(default)]
+    pub payload: Value,
+}
+
+/// Events by name.
+pub type Events = BTreeMap<String, Event>;
+
+#[derive(Serialize)]
+pub struct NamedEvent<'a> {
+    pub name: &'a str,
+    #[serde(flatten)]
+    pub event: &'a Event,
+}
+
+impl Event {
+    pub fn validate(&self, name: &str) -> Result<(), String> {
+        if name.is_empty()
+            || name.len() > NAME_MAX_LEN
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "an event name must have between 1 and {NAME_MAX_LEN} alphanumeric characters, '-' or '_'"
+            ));
+        }
+
+        if self.ends_at <= self.starts_at {
+            return Err("ends_at must be after starts_at".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
+
+/// Events running at `now`, ordered by name.
+pub fn active(events: &Events, now: DateTime<Utc>) -> Vec<NamedEvent<'_>> {
+    events
+        .iter()
+        .filter(|(_, event)| event.is_active(now))
+        .map(|(name, event)| NamedEvent { name, event })
+        .collect()
+}
+
+/// Next time an event starts or ends after `now`, when the active events change.
+pub fn next_change(events: &Events, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    events
+        .values()
+        .flat_map(|event| [event.starts_at, event.ends_at])
+        .filter(|date| *date > now)
+        .min()
+}
@@ -1,47 +1,131 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+
+use chrono::Utc;
 use futures::future::join_all;
 use octocrab::models::repos;
 use octocrab::repos::RepoHandler;
-use octocrab::{Octocrab, OctocrabBuilder};
+use octocrab::Octocrab;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 
-use crate::config::ApiConfig;
-use crate::game_data::{Asset, Assets, GameRelease, Repo};
+use crate::asset_names::{AssetName, AssetNameParser, DEFAULT_FLAVOR};
+use crate::config::{ApiConfig, GithubConfig};
+use crate::diagnostics::{self, AssetChecks, ValidationReport};
+use crate::game_data::{
+    Asset, Assets, DownloadStats, GameRelease, PackRelease, Platform, ReleaseNote, Repo,
+};
+use crate::github_client;
+use crate::trace_context;
 
 type Result<T> = std::result::Result<T, FetcherError>;
 
+/// Assets keyed by their filename stripped of any extension and build suffix.
+type RawAssets = HashMap<String, Asset>;
+
 pub struct Fetcher {
     octocrab: Octocrab,
     game_repo: Repo,
     updater_repo: Repo,
+    updater_filename: String,
+    updater_fallbacks: HashMap<Platform, Version>,
+    tag_prefix: String,
+    strict_tags: bool,
+    asset_names: AssetNameParser,
+    asset_checks: AssetChecks,
+    prerelease_backfill: bool,
+    /// Last game release scan of each flavor.
+    game_scans: Mutex<HashMap<String, ReleaseScan>>,
 
     checksum_fetcher: ChecksumFetcher,
 }
 
+/// Binaries resolved from the game releases up to the newest one, so the next scan only
+/// has to go through the releases published since.
+#[derive(Clone)]
+struct ReleaseScan {
+    pinned: Option<Version>,
+    newest: Version,
+    /// Changes when one of the scanned releases is deleted or has its assets replaced.
+    fingerprint: u64,
+    binaries: RawAssets,
+}
+
 struct ChecksumFetcher(reqwest::Client);
 
+/// Extra content distributed from its own repository, listed in `/game_version`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackConfig {
+    pub name: String,
+    /// Defaults to `github.repo_owner`.
+    pub owner: Option<String>,
+    pub repository: String,
+    /// Filename of the asset without its extension, `{platform}` is replaced by the
+    /// requested platform for packs built per platform.
+    pub asset_name: String,
+}
+
 #[derive(Debug)]
 pub enum FetcherError {
-    OctoError(octocrab::Error),
+    OctoError(Box<octocrab::Error>),
     ReqwestError(reqwest::Error),
-    InvalidSha256(usize),
+    EmptySha256,
+    /// Line of the checksum file, from 1.
+    InvalidSha256Line(usize),
+    InvalidSha256Hash(usize),
     WrongChecksum,
+    InvalidAssetPattern(regex::Error),
     NoReleaseFound,
+    PinnedReleaseNotFound(Version),
     InvalidVersion,
+    /// The GitHub personal access token can't be sent in a header.
+    InvalidToken,
 }
 
 impl Fetcher {
     pub fn from_config(config: &ApiConfig) -> Result<Self> {
-        let mut octocrab = OctocrabBuilder::default();
-        if let Some(github_pat) = &config.github_pat {
-            octocrab = octocrab.personal_token(github_pat.unsecure().to_string());
-        }
+        Self::with_source(config, &config.github)
+    }
+
+    /// Fetcher of the `shadow_github` source, when configured.
+    pub fn shadow_from_config(config: &ApiConfig) -> Option<Result<Self>> {
+        config
+            .shadow_github
+            .as_ref()
+            .map(|github| Self::with_source(config, github))
+    }
+
+    fn with_source(config: &ApiConfig, github: &GithubConfig) -> Result<Self> {
+        // GitHub rejects the requests without a user agent
+        let client = reqwest::Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()?;
+        let pat = github.pat.as_ref().map(|pat| pat.unsecure());
+        let octocrab =
+            github_client::build(client.clone(), pat).map_err(|_| FetcherError::InvalidToken)?;
 
         Ok(Self {
-            octocrab: octocrab.build()?,
-            game_repo: Repo::new(&config.repo_owner, &config.game_repository),
-            updater_repo: Repo::new(&config.repo_owner, &config.updater_repository),
+            octocrab,
+            game_repo: Repo::new(&github.repo_owner, &github.game_repository),
+            updater_repo: Repo::new(&github.repo_owner, &github.updater_repository),
+            updater_filename: github.updater_filename.clone(),
+            updater_fallbacks: config.updater.fallback_versions.clone(),
+            tag_prefix: github.tag_prefix.clone(),
+            strict_tags: github.strict_tags,
+            asset_names: AssetNameParser::new(&github.asset_patterns)?,
+            asset_checks: config.asset_checks.clone(),
 
-            checksum_fetcher: ChecksumFetcher::new(),
+            prerelease_backfill: config.game.prerelease_backfill,
+            game_scans: Mutex::new(HashMap::new()),
+            checksum_fetcher: ChecksumFetcher(client),
         })
     }
 
@@ -49,7 +133,35 @@ impl Fetcher {
         self.octocrab.repos(repo.owner(), repo.repository())
     }
 
-    pub async fn get_latest_game_release(&self) -> Result<GameRelease> {
+    /// Parses the version of a release listing, the releases whose tag isn't a version
+    /// are skipped, with a warning in strict mode.
+    fn listed_version(&self, release: &repos::Release) -> Option<Version> {
+        match parse_tag(&release.tag_name, &self.tag_prefix) {
+            Ok(version) => Some(version),
+            Err(err) if self.strict_tags => {
+                log::warn!(
+                    tag = release.tag_name.as_str();
+                    "skipping release {}: {err}",
+                    release.tag_name
+                );
+                None
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the binaries of the flavor from the latest game release, or the one tagged
+    /// with the pinned version, with the platforms it lacks filled from the previous releases,
+    /// then from the prereleases when enabled.
+    pub async fn get_latest_game_release(
+        &self,
+        pinned: Option<&Version>,
+        flavor: &str,
+    ) -> Result<GameRelease> {
+        // the game data is shared by every flavor
+        let keep =
+            |asset_name: &AssetName| asset_name.flavor == flavor || asset_name.name == "assets";
+
         let releases = self
             .on_repo(&self.game_repo)
             .releases()
@@ -57,37 +169,57 @@ impl Fetcher {
             .send()
             .await?;
 
-        let mut versions_released = releases
+        let (prereleases, releases): (Vec<_>, Vec<_>) =
+            releases.into_iter().partition(|r| r.prerelease);
+
+        let versions_released = releases
             .into_iter()
-            .filter(|r| !r.prerelease)
-            .filter_map(|r| Version::parse(&r.tag_name).ok().map(|v| (v, r)));
+            .filter_map(|r| self.listed_version(&r).map(|v| (v, r)))
+            .skip_while(|(version, _)| pinned.is_some_and(|pinned| version != pinned))
+            .collect::<Vec<_>>();
 
-        let Some((latest_version, latest_release)) = versions_released.next() else {
-            return Err(FetcherError::NoReleaseFound);
+        let Some((latest_version, latest_release)) = versions_released.first() else {
+            return Err(match pinned {
+                Some(pinned) => FetcherError::PinnedReleaseNotFound(pinned.clone()),
+                None => FetcherError::NoReleaseFound,
+            });
         };
 
-        let mut binaries = self
-            .get_assets_and_checksums(&latest_release.assets, &latest_version, None)
-            .await
-            .map(|((platform, mut asset), sha256)| {
-                asset.sha256 = match sha256 {
-                    Ok(sha256) => Some(sha256),
-                    Err(FetcherError::ReqwestError(_)) => None,
-                    Err(err) => return Err(err),
-                };
-
-                Ok((platform.to_string(), asset))
-            })
-            .collect::<Result<Assets>>()?;
+        // the releases resolved by the previous scan are reused as long as they're unchanged
+        let previous_scan = self
+            .game_scans
+            .lock()
+            .unwrap()
+            .get(flavor)
+            .filter(|scan| scan.pinned.as_ref() == pinned)
+            .cloned();
+        let (new_releases, previous_binaries) = match previous_scan {
+            Some(scan) => {
+                let known = versions_released
+                    .iter()
+                    .position(|(version, _)| *version <= scan.newest)
+                    .unwrap_or(versions_released.len());
+                match release_fingerprint(&versions_released[known..]) == scan.fingerprint {
+                    true => (&versions_released[..known], scan.binaries),
+                    false => (&versions_released[..], RawAssets::new()),
+                }
+            }
+            None => (&versions_released[..], RawAssets::new()),
+        };
 
-        for (version, release) in versions_released {
+        let mut binaries = RawAssets::new();
+        let mut complete = true;
+        for (version, release) in new_releases {
             for ((platform, mut asset), sha256) in self
-                .get_assets_and_checksums(&release.assets, &version, Some(&binaries))
+                .get_assets_and_checksums(&release.assets, version, Some(&binaries), keep)
                 .await
             {
                 asset.sha256 = match sha256 {
                     Ok(sha256) => Some(sha256),
-                    Err(FetcherError::ReqwestError(_)) => None,
+                    Err(FetcherError::ReqwestError(_)) => {
+                        complete = false;
+                        None
+                    }
                     Err(err) => return Err(err),
                 };
 
@@ -95,63 +227,380 @@ impl Fetcher {
             }
         }
 
+        for (platform, asset) in previous_binaries {
+            binaries.entry(platform).or_insert(asset);
+        }
+
+        // the checksums which couldn't be downloaded are tried again on the next scan
+        if complete {
+            let scan = ReleaseScan {
+                pinned: pinned.cloned(),
+                newest: latest_version.clone(),
+                fingerprint: release_fingerprint(&versions_released),
+                binaries: binaries.clone(),
+            };
+            self.game_scans
+                .lock()
+                .unwrap()
+                .insert(flavor.to_string(), scan);
+        }
+
+        // the first builds of a new platform may only be prereleases
+        if self.prerelease_backfill {
+            let prereleases = prereleases
+                .iter()
+                .filter(|r| !r.draft)
+                .filter_map(|r| self.listed_version(r).map(|v| (v, r)))
+                .filter(|(version, _)| pinned.is_none_or(|pinned| version <= pinned));
+            let keep = |asset_name: &AssetName| asset_name.name != "assets" && keep(asset_name);
+
+            for (version, release) in prereleases {
+                for ((platform, mut asset), sha256) in self
+                    .get_assets_and_checksums(&release.assets, &version, Some(&binaries), keep)
+                    .await
+                {
+                    asset.sha256 = match sha256 {
+                        Ok(sha256) => Some(sha256),
+                        Err(FetcherError::ReqwestError(_)) => None,
+                        Err(err) => return Err(err),
+                    };
+                    asset.prerelease = true;
+
+                    binaries.insert(platform.to_string(), asset);
+                }
+            }
+        }
+
         let latest_assets = binaries.remove("assets");
 
         match latest_assets {
             Some(assets) => Ok(GameRelease {
                 assets_version: assets.version.clone(),
                 assets,
-                binaries,
-                version: latest_version,
+                binaries: into_platform_assets(binaries, ""),
+                changelog: latest_release.body.clone(),
+                version: latest_version.clone(),
             }),
             None => Err(FetcherError::NoReleaseFound),
         }
     }
 
+    /// Returns the binaries of the latest updater release, the platforms it lacks or whose
+    /// checksum is broken are served from their fallback version when one is configured.
     pub async fn get_latest_updater_release(&self) -> Result<Assets> {
-        let last_release = self
+        let last_release = match self
             .on_repo(&self.updater_repo)
             .releases()
             .get_latest()
+            .await
+        {
+            Ok(release) => release,
+            // GitHub has no latest release until one is published
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code == 404 => {
+                return Err(FetcherError::NoReleaseFound)
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let version = parse_tag(&last_release.tag_name, &self.tag_prefix)?;
+        let mut assets = self.get_updater_assets(&last_release, &version).await?;
+
+        // each fallback release is only fetched once, whatever its number of platforms
+        let mut fallbacks = BTreeMap::<&Version, Vec<Platform>>::new();
+        for (platform, fallback_version) in &self.updater_fallbacks {
+            if !assets.contains_key(platform) && *fallback_version != version {
+                fallbacks
+                    .entry(fallback_version)
+                    .or_default()
+                    .push(*platform);
+            }
+        }
+
+        if fallbacks.is_empty() {
+            return Ok(assets);
+        }
+
+        let releases = match self
+            .on_repo(&self.updater_repo)
+            .releases()
+            .list()
+            .per_page(100)
+            .send()
+            .await
+        {
+            Ok(releases) => releases.items,
+            Err(err) => {
+                log::warn!("failed to list the fallback updater releases: {err}");
+                return Ok(assets);
+            }
+        };
+
+        for (fallback_version, platforms) in fallbacks {
+            let Some(release) = self.find_release(&releases, fallback_version) else {
+                log::warn!("no updater release tagged {fallback_version}");
+                continue;
+            };
+            let mut fallback_assets = match self.get_updater_assets(release, fallback_version).await
+            {
+                Ok(fallback_assets) => fallback_assets,
+                Err(err) => {
+                    log::warn!("failed to fetch the fallback updater {fallback_version}: {err}");
+                    continue;
+                }
+            };
+
+            for platform in platforms {
+                match fallback_assets.remove(&platform) {
+                    Some(asset) => {
+                        log::warn!(
+                            "updater {version} has no valid {platform} binary, serving {fallback_version}"
+                        );
+                        assets.insert(platform, asset);
+                    }
+                    None => log::warn!(
+                        "fallback updater {fallback_version} has no valid {platform} binary"
+                    ),
+                }
+            }
+        }
+
+        Ok(assets)
+    }
+
+    /// Finds the release whose tag parses as the version, the tag may lack the prefix.
+    fn find_release<'a>(
+        &self,
+        releases: &'a [repos::Release],
+        version: &Version,
+    ) -> Option<&'a repos::Release> {
+        releases.iter().find(|release| {
+            parse_tag(&release.tag_name, &self.tag_prefix).is_ok_and(|tag| tag == *version)
+        })
+    }
+
+    /// Resolves the updater binaries of a release, the ones whose checksum is broken are
+    /// left out when their platform has a fallback version instead of failing the release.
+    async fn get_updater_assets(
+        &self,
+        release: &repos::Release,
+        version: &Version,
+    ) -> Result<Assets> {
+        let updater_suffix = format!("_{}", self.updater_filename);
+        let has_fallback = |name: &str| {
+            let platform = name.strip_suffix(&updater_suffix).unwrap_or(name);
+            platform
+                .parse::<Platform>()
+                .is_ok_and(|platform| self.updater_fallbacks.contains_key(&platform))
+        };
+
+        let mut assets = RawAssets::new();
+        for ((platform, mut asset), sha256) in self
+            .get_assets_and_checksums(&release.assets, version, None, is_default_flavor)
+            .await
+        {
+            asset.sha256 = match sha256 {
+                Ok(sha256) => Some(sha256),
+                Err(FetcherError::ReqwestError(_)) => None,
+                Err(err) if has_fallback(&platform) => {
+                    log::warn!("ignoring updater asset {}: {err}", asset.name);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+
+            assets.insert(platform.to_string(), asset);
+        }
+
+        Ok(into_platform_assets(assets, &updater_suffix))
+    }
+
+    pub async fn get_latest_pack_release(&self, pack: &PackConfig) -> Result<PackRelease> {
+        let owner = pack.owner.as_deref().unwrap_or(self.game_repo.owner());
+        let last_release = self
+            .on_repo(&Repo::new(owner, &pack.repository))
+            .releases()
+            .get_latest()
             .await?;
 
-        let version = Version::parse(&last_release.tag_name)?;
+        let version = parse_tag(&last_release.tag_name, &self.tag_prefix)?;
 
-        self.get_assets_and_checksums(&last_release.assets, &version, None)
+        let assets = self
+            .get_assets_and_checksums(&last_release.assets, &version, None, is_default_flavor)
             .await
-            .map(|((platform, mut asset), sha256)| {
+            .map(|((name, mut asset), sha256)| {
                 asset.sha256 = match sha256 {
                     Ok(sha256) => Some(sha256),
                     Err(FetcherError::ReqwestError(_)) => None,
                     Err(err) => return Err(err),
                 };
 
-                Ok((platform.to_string(), asset))
+                Ok((name.to_string(), asset))
+            })
+            .collect::<Result<RawAssets>>()?;
+
+        Ok(PackRelease { assets, version })
+    }
+
+    /// Lists the published game releases, newest first.
+    pub async fn get_game_release_notes(&self) -> Result<Vec<ReleaseNote>> {
+        let releases = self
+            .on_repo(&self.game_repo)
+            .releases()
+            .list()
+            .send()
+            .await?;
+
+        Ok(releases
+            .into_iter()
+            .filter(|r| !r.prerelease && !r.draft)
+            .filter_map(|release| {
+                let version = self.listed_version(&release)?;
+                Some(ReleaseNote {
+                    title: release
+                        .name
+                        .filter(|name| !name.trim().is_empty())
+                        .unwrap_or_else(|| format!("{} {version}", self.game_repo.repository())),
+                    published_at: release.published_at.or(release.created_at)?,
+                    body: release.body,
+                    url: release.html_url.to_string(),
+                    version,
+                })
             })
-            .collect::<Result<Assets>>()
+            .collect())
+    }
+
+    pub async fn get_game_download_stats(&self) -> Result<DownloadStats> {
+        let releases = self
+            .on_repo(&self.game_repo)
+            .releases()
+            .list()
+            .send()
+            .await?;
+
+        let mut versions = BTreeMap::<String, BTreeMap<String, i64>>::new();
+        for release in releases.into_iter().filter(|r| !r.prerelease) {
+            let Some(version) = self.listed_version(&release) else {
+                continue;
+            };
+
+            let platforms = versions.entry(version.to_string()).or_default();
+            for asset in release
+                .assets
+                .iter()
+                .filter(|a| !a.name.ends_with(".sha256"))
+            {
+                let platform = match self.asset_names.parse(&asset.name) {
+                    Some(asset_name) => asset_name.name.into_owned(),
+                    None => asset.name.clone(),
+                };
+                *platforms.entry(platform).or_default() += asset.download_count;
+            }
+        }
+
+        Ok(DownloadStats {
+            timestamp: Utc::now(),
+            total: versions.values().flat_map(BTreeMap::values).sum(),
+            versions,
+        })
+    }
+
+    /// Inspects the game releases and the latest updater release for packaging mistakes,
+    /// without fetching the checksums.
+    pub async fn validate_releases(&self) -> Result<ValidationReport> {
+        let game_releases = self
+            .on_repo(&self.game_repo)
+            .releases()
+            .list()
+            .send()
+            .await?;
+
+        let mut game = game_releases
+            .items
+            .iter()
+            .map(|release| {
+                diagnostics::inspect_release(
+                    release,
+                    &self.tag_prefix,
+                    &self.asset_names,
+                    &self.asset_checks,
+                    "",
+                    &["assets"],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // the platforms missing from the latest release are served from older ones
+        let latest = game_releases
+            .items
+            .iter()
+            .position(|r| !r.prerelease && parse_tag(&r.tag_name, &self.tag_prefix).is_ok());
+        if let Some(latest) = latest {
+            let platforms =
+                diagnostics::release_platforms(&game_releases.items[latest], &self.asset_names, "");
+            for platform in Platform::ALL.iter().filter(|p| !platforms.contains(p)) {
+                game[latest]
+                    .issues
+                    .push(format!("latest release has no {platform} binary"));
+            }
+        }
+
+        let updater_suffix = format!("_{}", self.updater_filename);
+        let updater = match self
+            .on_repo(&self.updater_repo)
+            .releases()
+            .get_latest()
+            .await
+        {
+            Ok(release) => Some(diagnostics::inspect_release(
+                &release,
+                &self.tag_prefix,
+                &self.asset_names,
+                &self.asset_checks,
+                &updater_suffix,
+                &[],
+            )),
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code == 404 => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(ValidationReport { game, updater })
     }
 
     async fn get_assets_and_checksums<'a: 'b, 'b, A>(
         &self,
         assets: A,
         version: &Version,
-        binaries: Option<&Assets>,
-    ) -> impl Iterator<Item = ((&'b str, Asset), Result<String>)>
+        binaries: Option<&RawAssets>,
+        keep: impl Fn(&AssetName) -> bool,
+    ) -> impl Iterator<Item = ((Cow<'b, str>, Asset), Result<String>)>
     where
         A: IntoIterator<Item = &'a repos::Asset>,
     {
         let assets = assets
             .into_iter()
+            .filter(|asset| !asset.name.ends_with(".sha256"))
             .filter_map(|asset| {
-                let platform = remove_game_suffix(asset.name.as_str());
-                match !asset.name.ends_with(".sha256")
-                    && !binaries.is_some_and(|b| b.contains_key(platform))
+                let Some(asset_name) = self.asset_names.parse(&asset.name) else {
+                    log::warn!("ignoring asset {}: it matches no asset pattern", asset.name);
+                    return None;
+                };
+
+                if self.asset_checks.strict {
+                    let issues = self.asset_checks.issues(asset);
+                    if !issues.is_empty() {
+                        log::warn!("ignoring suspicious asset: {}", issues.join(", "));
+                        return None;
+                    }
+                }
+
+                match keep(&asset_name)
+                    && !binaries.is_some_and(|b| b.contains_key(&*asset_name.name))
                 {
-                    true => Some((platform, Asset::with_version(asset, version.clone()))),
+                    true => Some((asset_name.name, Asset::with_version(asset, version.clone()))),
                     false => None,
                 }
             })
-            .collect::<Vec<(&str, Asset)>>();
+            .collect::<Vec<(Cow<str>, Asset)>>();
 
         let checksums = join_all(
             assets
@@ -165,14 +614,9 @@ impl Fetcher {
 }
 
 impl ChecksumFetcher {
-    fn new() -> Self {
-        Self(reqwest::Client::new())
-    }
-
     async fn resolve(&self, asset: &Asset) -> Result<String> {
-        let response = self
-            .0
-            .get(format!("{}.sha256", asset.download_url))
+        let request = self.0.get(format!("{}.sha256", asset.download_url));
+        let response = trace_context::propagate(request)
             .send()
             .await?
             .text()
@@ -180,23 +624,91 @@ impl ChecksumFetcher {
         self.parse_response(asset.name.as_str(), response.as_str())
     }
 
+    /// Reads the checksum of the asset from a sha256sum (GNU) or BSD style file, which may
+    /// list several files. A lone hash is taken as the checksum of the asset.
     fn parse_response(&self, asset_name: &str, response: &str) -> Result<String> {
-        let parts: Vec<_> = response.split_whitespace().collect();
-        if parts.len() != 2 {
-            return Err(FetcherError::InvalidSha256(parts.len()));
+        let mut entries = 0;
+        for (i, line) in response.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            entries += 1;
+
+            let (sha256, filename) =
+                parse_checksum_line(line).ok_or(FetcherError::InvalidSha256Line(i + 1))?;
+            if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(FetcherError::InvalidSha256Hash(i + 1));
+            }
+
+            // files generated from another directory list paths
+            let matches = match filename {
+                Some(filename) => filename.rsplit('/').next() == Some(asset_name),
+                None => true,
+            };
+            if matches {
+                return Ok(sha256.to_ascii_lowercase());
+            }
+        }
+
+        match entries {
+            0 => Err(FetcherError::EmptySha256),
+            _ => Err(FetcherError::WrongChecksum),
+        }
+    }
+}
+
+/// Splits `SHA256 (name) = hash`, `hash *name`, `hash  name` or `hash` into the hash and
+/// the filename.
+fn parse_checksum_line(line: &str) -> Option<(&str, Option<&str>)> {
+    if let Some(rest) = line.strip_prefix("SHA256 (") {
+        let (filename, sha256) = rest.rsplit_once(") = ")?;
+        return Some((sha256.trim(), Some(filename)));
+    }
+
+    match line.split_once(' ') {
+        // the marker tells whether the file was read in binary or text mode
+        Some((sha256, filename)) => {
+            let filename = filename.strip_prefix(['*', ' ']).unwrap_or(filename);
+            (!filename.is_empty()).then_some((sha256, Some(filename)))
         }
+        None => Some((line, None)),
+    }
+}
 
-        let (sha256, filename) = (parts[0], parts[1]);
-        match !filename.starts_with('*') || &filename[1..] != asset_name {
-            false => Ok(sha256.to_string()),
-            true => Err(FetcherError::WrongChecksum),
+impl fmt::Display for FetcherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // octocrab appends a backtrace to its messages, the source is enough
+            FetcherError::OctoError(err) => match std::error::Error::source(err.as_ref()) {
+                Some(source) => write!(f, "github error: {source}"),
+                None => write!(f, "github error: {err}"),
+            },
+            FetcherError::ReqwestError(err) => write!(f, "http error: {err}"),
+            FetcherError::EmptySha256 => write!(f, "empty sha256 file"),
+            FetcherError::InvalidSha256Line(line) => {
+                write!(f, "invalid sha256 file: line {line} has an unknown format")
+            }
+            FetcherError::InvalidSha256Hash(line) => {
+                write!(f, "invalid sha256 file: line {line} is not a sha256 hash")
+            }
+            FetcherError::WrongChecksum => write!(f, "checksum file doesn't list the asset"),
+            FetcherError::InvalidAssetPattern(err) => write!(f, "invalid asset pattern: {err}"),
+            FetcherError::NoReleaseFound => write!(f, "no release found"),
+            FetcherError::PinnedReleaseNotFound(version) => {
+                write!(f, "no release tagged {version}")
+            }
+            FetcherError::InvalidVersion => write!(f, "invalid version tag"),
+            FetcherError::InvalidToken => write!(f, "invalid github personal access token"),
         }
     }
 }
 
+impl std::error::Error for FetcherError {}
+
 impl From<octocrab::Error> for FetcherError {
     fn from(err: octocrab::Error) -> Self {
-        FetcherError::OctoError(err)
+        FetcherError::OctoError(Box::new(err))
     }
 }
 
@@ -206,17 +718,349 @@ impl From<reqwest::Error> for FetcherError {
     }
 }
 
+impl From<regex::Error> for FetcherError {
+    fn from(err: regex::Error) -> Self {
+        FetcherError::InvalidAssetPattern(err)
+    }
+}
+
 impl From<semver::Error> for FetcherError {
     fn from(_: semver::Error) -> Self {
         FetcherError::InvalidVersion
     }
 }
 
-fn remove_game_suffix(asset_name: &str) -> &str {
-    let platform = asset_name
-        .find('.')
-        .map_or(asset_name, |pos| &asset_name[..pos]);
-    platform
-        .find("_releasedbg")
-        .map_or(platform, |pos| &platform[..pos])
+/// Keys the assets by platform, dropping the ones which don't belong to a known platform.
+fn into_platform_assets(assets: RawAssets, suffix: &str) -> Assets {
+    assets
+        .into_iter()
+        .filter_map(|(name, asset)| {
+            let platform = name.strip_suffix(suffix).unwrap_or(&name);
+            match platform.parse::<Platform>() {
+                Ok(platform) => Some((platform, asset)),
+                Err(err) => {
+                    log::warn!("ignoring asset {}: {err}", asset.name);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn release_fingerprint(releases: &[(Version, repos::Release)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (_, release) in releases {
+        release.id.hash(&mut hasher);
+        for asset in &release.assets {
+            asset.id.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn is_default_flavor(asset_name: &AssetName) -> bool {
+    asset_name.flavor == DEFAULT_FLAVOR
+}
+
+/// Parses a release tag as a version, once stripped of the prefix when it has it.
+pub(crate) fn parse_tag(tag: &str, prefix: &str) -> std::result::Result<Version, semver::Error> {
+    Version::parse(tag.strip_prefix(prefix).unwrap_or(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn release(tag_name: &str) -> repos::Release {
+        serde_json::from_value(serde_json::json!({
+            "url": "https://api.github.com/repos/o/r/releases/1",
+            "html_url": "https://github.com/o/r/releases/1",
+            "assets_url": "https://api.github.com/repos/o/r/releases/1/assets",
+            "upload_url": "https://uploads.github.com/repos/o/r/releases/1/assets",
+            "id": 1,
+            "node_id": "R_1",
+            "tag_name": tag_name,
+            "target_commitish": "main",
+            "draft": false,
+            "prerelease": false,
+            "assets": [],
+        }))
+        .unwrap()
+    }
+
+    fn fetcher(tag_prefix: &str, strict_tags: bool) -> Fetcher {
+        let mut config = ApiConfig::default();
+        config.github.tag_prefix = tag_prefix.to_string();
+        config.github.strict_tags = strict_tags;
+        Fetcher::from_config(&config).unwrap()
+    }
+
+    fn parse_checksum(asset_name: &str, response: &str) -> Result<String> {
+        ChecksumFetcher(reqwest::Client::new()).parse_response(asset_name, response)
+    }
+
+    #[test]
+    fn checksum_whitespace_variants() {
+        let responses = [
+            format!("{HASH}  game.zip"),
+            format!("{HASH} *game.zip"),
+            // written by hand, without the mode marker
+            format!("{HASH} game.zip"),
+            format!("   {HASH}  game.zip   "),
+            format!("{HASH}  game.zip\n"),
+            format!("{HASH}  game.zip\r\n"),
+            format!("\r\n\n{HASH}  game.zip\r\n\r\n"),
+            format!("{HASH}\n"),
+            format!("{HASH}\r\n"),
+            format!("SHA256 (game.zip) = {HASH}\r\n"),
+        ];
+
+        for response in &responses {
+            assert_eq!(
+                parse_checksum("game.zip", response).ok().as_deref(),
+                Some(HASH),
+                "{response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn checksum_rejects_invalid_hashes() {
+        let responses = [
+            format!("{}  game.zip", "z".repeat(64)),
+            format!("{}  game.zip", &HASH[..63]),
+            format!("{HASH}0  game.zip"),
+            format!("{}  game.zip", "é".repeat(32)),
+            // sha256sum never separates with a tab
+            format!("{HASH}\tgame.zip"),
+            format!("SHA256 (game.zip) = {}", "g".repeat(64)),
+        ];
+
+        for response in &responses {
+            assert!(
+                matches!(
+                    parse_checksum("game.zip", response),
+                    Err(FetcherError::InvalidSha256Hash(1))
+                ),
+                "{response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn checksum_unicode_filenames() {
+        let cases = [
+            ("ゲーム.zip", format!("{HASH}  ゲーム.zip")),
+            (
+                "données_élève.tar.gz",
+                format!("{HASH} *données_élève.tar.gz"),
+            ),
+            ("jeu ✓.zip", format!("{HASH}  jeu ✓.zip")),
+            ("ゲーム.zip", format!("SHA256 (ゲーム.zip) = {HASH}")),
+            ("ゲーム.zip", format!("{HASH}  dist/ゲーム.zip")),
+        ];
+
+        for (asset_name, response) in &cases {
+            assert_eq!(
+                parse_checksum(asset_name, response).ok().as_deref(),
+                Some(HASH),
+                "{response:?}"
+            );
+        }
+
+        // only the exact name matches, not another normalization of it
+        assert!(matches!(
+            parse_checksum("ゲーム.zip", &format!("{HASH}  ケーム.zip")),
+            Err(FetcherError::WrongChecksum)
+        ));
+    }
+
+    #[test]
+    fn checksum_formats() {
+        let formats = [
+            // GNU, text and binary modes
+            format!("{HASH}  game.zip"),
+            format!("{HASH} *game.zip"),
+            // BSD, as written by shasum --tag
+            format!("SHA256 (game.zip) = {HASH}"),
+            // lone hash
+            HASH.to_string(),
+        ];
+
+        for response in &formats {
+            assert_eq!(
+                parse_checksum("game.zip", response).ok().as_deref(),
+                Some(HASH),
+                "{response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn checksum_multi_file_lookup() {
+        let other = "a".repeat(64);
+        let response = format!(
+            "{other}  linux_x86_64.tar.gz\n\
+             SHA256 (game.zip) = {HASH}\n\
+             {other} *windows_x64.zip\n"
+        );
+        assert_eq!(parse_checksum("game.zip", &response).unwrap(), HASH);
+        assert_eq!(parse_checksum("windows_x64.zip", &response).unwrap(), other);
+
+        // files generated from another directory list paths
+        let response = format!("{other}  build/game.zip.sig\n{HASH}  build/out/game.zip\n");
+        assert_eq!(parse_checksum("game.zip", &response).unwrap(), HASH);
+
+        // a lone hash among named entries isn't skipped
+        let response = format!("{other}  linux_x86_64.tar.gz\n{HASH}\n");
+        assert_eq!(parse_checksum("game.zip", &response).unwrap(), HASH);
+    }
+
+    #[test]
+    fn checksum_errors() {
+        assert!(matches!(
+            parse_checksum("game.zip", ""),
+            Err(FetcherError::EmptySha256)
+        ));
+        assert!(matches!(
+            parse_checksum("game.zip", " \r\n\n\t\n"),
+            Err(FetcherError::EmptySha256)
+        ));
+
+        assert!(matches!(
+            parse_checksum(
+                "game.zip",
+                &format!("{HASH}  other.zip\n{HASH}  other2.zip")
+            ),
+            Err(FetcherError::WrongChecksum)
+        ));
+    }
+
+    #[test]
+    fn checksum_error_lines() {
+        let other = "a".repeat(64);
+        // blank lines are counted, from 1
+        let cases = [
+            (format!("SHA256 (game.zip) {HASH}"), 1),
+            (format!("{other}  a.zip\n\n{HASH} *"), 3),
+            (format!("{other}  a.zip\r\nSHA256 (game.zip = {HASH}"), 2),
+        ];
+        for (response, line) in &cases {
+            assert!(
+                matches!(
+                    parse_checksum("game.zip", response),
+                    Err(FetcherError::InvalidSha256Line(l)) if l == *line
+                ),
+                "{response:?}"
+            );
+        }
+
+        let cases = [
+            (format!("{}  game.zip", "z".repeat(64)), 1),
+            (format!("{other}  a.zip\n\n{}  game.zip", &HASH[1..]), 3),
+            (
+                format!("\n{other}  a.zip\nSHA256 (game.zip) = {}", "x".repeat(64)),
+                3,
+            ),
+        ];
+        for (response, line) in &cases {
+            assert!(
+                matches!(
+                    parse_checksum("game.zip", response),
+                    Err(FetcherError::InvalidSha256Hash(l)) if l == *line
+                ),
+                "{response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn checksum_is_lowercased() {
+        let response = format!("{}  game.zip", HASH.to_ascii_uppercase());
+        assert_eq!(parse_checksum("game.zip", &response).unwrap(), HASH);
+    }
+
+    #[test]
+    fn tags() {
+        let cases = [
+            ("0.5.1", "", Some("0.5.1")),
+            ("v0.5.1", "v", Some("0.5.1")),
+            // the prefix is optional, so a tag written without it still counts
+            ("0.5.1", "v", Some("0.5.1")),
+            ("release-1.0.0", "release-", Some("1.0.0")),
+            ("v1.0.0-rc.1", "v", Some("1.0.0-rc.1")),
+            ("v1.0.0+build.42", "v", Some("1.0.0+build.42")),
+            (
+                "1.0.0-beta+exp.sha.5114f85",
+                "",
+                Some("1.0.0-beta+exp.sha.5114f85"),
+            ),
+            ("v0.5.1", "", None),
+            ("vv0.5.1", "v", None),
+            ("V0.5.1", "v", None),
+            ("0.5", "", None),
+            ("0.5.1.2", "", None),
+            ("05.1.0", "", None),
+            (" 0.5.1", "", None),
+            ("nightly", "", None),
+            ("1.0.0+", "", None),
+            ("", "", None),
+        ];
+
+        for (tag, prefix, version) in cases {
+            assert_eq!(
+                parse_tag(tag, prefix).ok(),
+                version.map(|v| Version::parse(v).unwrap()),
+                "{tag:?} with prefix {prefix:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn build_metadata_is_kept() {
+        let version = parse_tag("v1.2.3+build.7", "v").unwrap();
+        assert_eq!(version.build.as_str(), "build.7");
+        assert_eq!(version.to_string(), "1.2.3+build.7");
+        // only the precedence ignores it
+        assert_eq!(
+            version.cmp_precedence(&Version::new(1, 2, 3)),
+            std::cmp::Ordering::Equal
+        );
+    }
+
+    #[actix_web::test]
+    async fn listed_versions() {
+        for strict_tags in [false, true] {
+            let fetcher = fetcher("v", strict_tags);
+            assert_eq!(
+                fetcher.listed_version(&release("v0.5.1")),
+                Some(Version::new(0, 5, 1))
+            );
+            assert_eq!(
+                fetcher.listed_version(&release("v0.6.0+linux")),
+                Some(Version::parse("0.6.0+linux").unwrap())
+            );
+            // malformed tags are skipped, strict mode only warns about them
+            assert_eq!(fetcher.listed_version(&release("v0.5")), None);
+            assert_eq!(fetcher.listed_version(&release("latest")), None);
+        }
+    }
+
+    /// Fallback updater releases are found by their parsed tag, which may lack the prefix.
+    #[actix_web::test]
+    async fn fallback_releases_are_found_by_version() {
+        let fetcher = fetcher("v", false);
+        let releases = [release("nightly"), release("v1.3.0"), release("1.2.0")];
+        let tag_of = |version: &str| {
+            fetcher
+                .find_release(&releases, &Version::parse(version).unwrap())
+                .map(|release| release.tag_name.as_str())
+        };
+
+        assert_eq!(tag_of("1.3.0"), Some("v1.3.0"));
+        assert_eq!(tag_of("1.2.0"), Some("1.2.0"));
+        assert_eq!(tag_of("1.1.0"), None);
+    }
 }
@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use octocrab::models::repos;
 use semver::Version;
-use serde::Serialize;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::downtime::DowntimeNotice;
 
 #[derive(Clone, Serialize)]
 pub struct Asset {
@@ -12,22 +17,57 @@ pub struct Asset {
     pub name: String,
     #[serde(skip_serializing)]
     pub version: Version,
+    #[serde(skip_serializing)]
+    pub content_type: String,
     pub download_url: String,
     pub sha256: Option<String>,
+    /// Set on the binaries of platforms which only have prerelease builds yet.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub prerelease: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Platform {
+    WindowsX64,
+    WindowsArm64,
+    LinuxX86_64,
+    LinuxAarch64,
+    MacosX86_64,
+    MacosArm64,
+    MacosUniversal,
 }
 
+#[derive(Debug)]
+pub struct UnknownPlatform(pub String);
+
 pub struct Repo {
     owner: String,
     repository: String,
 }
 
-pub type Assets = HashMap<String, Asset>;
+pub type Assets = HashMap<Platform, Asset>;
 
 #[derive(Clone)]
 pub struct GameRelease {
     pub assets: Asset,
     pub assets_version: Version,
     pub binaries: Assets,
+    pub changelog: Option<String>,
+    pub version: Version,
+}
+
+/// Published game release, as listed in the releases feed.
+pub struct ReleaseNote {
+    pub body: Option<String>,
+    pub published_at: DateTime<Utc>,
+    pub title: String,
+    pub url: String,
+    pub version: Version,
+}
+
+/// Latest release of a content pack, its assets keyed by filename without extension.
+pub struct PackRelease {
+    pub assets: HashMap<String, Asset>,
     pub version: Version,
 }
 
@@ -36,8 +76,28 @@ pub struct GameVersion {
     pub assets: Asset,
     pub assets_version: String,
     pub binaries: Asset,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub packs: BTreeMap<String, Asset>,
     pub updater: Asset,
     pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downtime: Option<DowntimeNotice>,
+}
+
+/// Download counts of every game release, keyed by version then by platform.
+#[derive(Serialize, Deserialize)]
+pub struct DownloadStats {
+    pub timestamp: DateTime<Utc>,
+    pub total: i64,
+    pub versions: BTreeMap<String, BTreeMap<String, i64>>,
+}
+
+#[derive(Serialize)]
+pub struct UpdaterVersion {
+    pub version: String,
+    pub download_url: String,
+    pub sha256: Option<String>,
+    pub size: i64,
 }
 
 impl Asset {
@@ -45,13 +105,93 @@ impl Asset {
         Self {
             size: asset.size,
             name: asset.name.clone(),
+            content_type: asset.content_type.clone(),
             download_url: asset.browser_download_url.to_string(),
             sha256: None,
+            prerelease: false,
             version,
         }
     }
 }
 
+impl From<&Asset> for UpdaterVersion {
+    fn from(asset: &Asset) -> Self {
+        Self {
+            version: asset.version.to_string(),
+            download_url: asset.download_url.clone(),
+            sha256: asset.sha256.clone(),
+            size: asset.size,
+        }
+    }
+}
+
+impl Platform {
+    pub const ALL: [Platform; 7] = [
+        Platform::WindowsX64,
+        Platform::WindowsArm64,
+        Platform::LinuxX86_64,
+        Platform::LinuxAarch64,
+        Platform::MacosX86_64,
+        Platform::MacosArm64,
+        Platform::MacosUniversal,
+    ];
+
+    /// Name of the platform as it appears in asset filenames and queries.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::WindowsX64 => "windows_x64",
+            Platform::WindowsArm64 => "windows_arm64",
+            Platform::LinuxX86_64 => "linux_x86_64",
+            Platform::LinuxAarch64 => "linux_aarch64",
+            Platform::MacosX86_64 => "macos_x86_64",
+            Platform::MacosArm64 => "macos_arm64",
+            Platform::MacosUniversal => "macos_universal",
+        }
+    }
+
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        Self::ALL.iter().map(Platform::as_str)
+    }
+}
+
+impl FromStr for Platform {
+    type Err = UnknownPlatform;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|platform| platform.as_str() == s)
+            .ok_or_else(|| UnknownPlatform(s.to_string()))
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Platform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let platform = String::deserialize(deserializer)?;
+        platform.parse().map_err(de::Error::custom)
+    }
+}
+
+impl fmt::Display for UnknownPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown platform \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for UnknownPlatform {}
+
 impl Repo {
     pub fn new<O: ToString, R: ToString>(owner: O, repository: R) -> Self {
         Self {
@@ -68,3 +208,32 @@ impl Repo {
         self.repository.as_str()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_names_round_trip() {
+        for platform in Platform::ALL {
+            assert_eq!(platform.as_str().parse::<Platform>().unwrap(), platform);
+            assert_eq!(platform.to_string(), platform.as_str());
+        }
+    }
+
+    #[test]
+    fn unknown_platforms_are_rejected() {
+        for name in [
+            "",
+            "windows",
+            "Windows_x64",
+            "windows_x64 ",
+            " windows_x64",
+            "windows_x64_releasedbg",
+            "linux-x86_64",
+            "lïnux_x86_64",
+        ] {
+            assert!(name.parse::<Platform>().is_err(), "{name:?}");
+        }
+    }
+}
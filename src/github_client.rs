@@ -0,0 +1,137 @@
+//! Octocrab sending its requests through reqwest, so the calls to GitHub carry the
+//! `traceparent` of the request being served.
+
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use http::{Request, Response, Uri};
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::base_uri::BaseUriLayer;
+use octocrab::{AuthState, Octocrab, OctocrabBuilder};
+use reqwest::header::{HeaderValue, InvalidHeaderValue};
+use tower::Service;
+
+use crate::trace_context::{TraceContext, TRACEPARENT_HEADER};
+
+const GITHUB_API: &str = "https://api.github.com";
+/// Retries of the requests which fail or get a server error, like octocrab does.
+const MAX_RETRIES: usize = 3;
+
+#[derive(Clone)]
+struct TracedService(reqwest::Client);
+
+pub fn build(client: reqwest::Client, pat: Option<&str>) -> Result<Octocrab, InvalidHeaderValue> {
+    with_base_uri(client, pat, Uri::from_static(GITHUB_API))
+}
+
+fn with_base_uri(
+    client: reqwest::Client,
+    pat: Option<&str>,
+    base_uri: Uri,
+) -> Result<Octocrab, InvalidHeaderValue> {
+    let auth_header = pat
+        .map(|pat| HeaderValue::from_str(&format!("Bearer {pat}")))
+        .transpose()?;
+
+    let octocrab = OctocrabBuilder::new_empty()
+        .with_service(TracedService(client))
+        .with_layer(&BaseUriLayer::new(base_uri.clone()))
+        // only sent to the API, not to the hosts it redirects to
+        .with_layer(&AuthHeaderLayer::new(auth_header, base_uri))
+        .with_auth(AuthState::None)
+        .build();
+    Ok(octocrab.unwrap_or_else(|never| match never {}))
+}
+
+impl Service<Request<String>> for TracedService {
+    type Response = Response<reqwest::Body>;
+    type Error = reqwest::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        let client = self.0.clone();
+        // octocrab calls the service from the task of its buffer, the returned future is
+        // polled by the task awaiting the response, which holds the trace context
+        Box::pin(async move {
+            let mut request = reqwest::Request::try_from(request)?;
+            if let Some(trace_context) = TraceContext::current() {
+                if let Ok(traceparent) = HeaderValue::from_str(&trace_context.traceparent()) {
+                    request
+                        .headers_mut()
+                        .insert(TRACEPARENT_HEADER, traceparent);
+                }
+            }
+
+            let mut retries = 0;
+            loop {
+                let attempt = request
+                    .try_clone()
+                    .expect("the body of octocrab requests is a string");
+                match client.execute(attempt).await {
+                    Ok(response)
+                        if retries < MAX_RETRIES
+                            && (response.status().is_server_error()
+                                || response.status() == 429) => {}
+                    Err(_) if retries < MAX_RETRIES => {}
+                    result => return result.map(Response::from),
+                }
+                retries += 1;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    /// Answers one request with an empty JSON object, returning its headers.
+    fn serve_once(listener: TcpListener) -> thread::JoinHandle<Vec<String>> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let headers = BufReader::new(stream.try_clone().unwrap())
+                .lines()
+                .map(Result::unwrap)
+                .take_while(|line| !line.is_empty())
+                .map(|line| line.to_lowercase())
+                .collect();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{}")
+                .unwrap();
+            headers
+        })
+    }
+
+    #[actix_web::test]
+    async fn requests_carry_the_trace_and_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_uri = format!("http://{}", listener.local_addr().unwrap());
+        let server = serve_once(listener);
+
+        let octocrab = with_base_uri(
+            reqwest::Client::new(),
+            Some("github_pat"),
+            base_uri.parse().unwrap(),
+        )
+        .unwrap();
+        let trace_context = TraceContext::from_traceparent(None);
+        let traceparent = trace_context.traceparent();
+        let response: serde_json::Value = trace_context
+            .scope(octocrab.get("/rate_limit", None::<&()>))
+            .await
+            .unwrap();
+        assert_eq!(response, serde_json::json!({}));
+
+        let headers = server.join().unwrap();
+        assert!(headers.contains(&format!("traceparent: {traceparent}")));
+        assert!(headers.contains(&"authorization: bearer github_pat".to_string()));
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, HashMap};
+use std::{fmt, fs, io};
+
+use actix_web::http::header::{AcceptLanguage, Header, Preference};
+use actix_web::HttpRequest;
+
+/// Language of the descriptions written in the code, used when no catalog matches.
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Translated error descriptions, keyed by language then by error code.
+#[derive(Default)]
+pub struct Catalogs {
+    languages: HashMap<String, HashMap<String, String>>,
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Io(String, io::Error),
+    Parse(String, toml::de::Error),
+}
+
+impl Catalogs {
+    /// Loads the catalogs from TOML files mapping error codes to descriptions.
+    pub fn load(paths: &BTreeMap<String, String>) -> Result<Self, CatalogError> {
+        let mut languages = HashMap::new();
+        for (language, path) in paths {
+            let content =
+                fs::read_to_string(path).map_err(|err| CatalogError::Io(path.clone(), err))?;
+            let messages =
+                toml::from_str(&content).map_err(|err| CatalogError::Parse(path.clone(), err))?;
+            languages.insert(language.to_ascii_lowercase(), messages);
+        }
+
+        Ok(Self { languages })
+    }
+
+    /// Returns the description of the code in the language the client prefers, along
+    /// with that language, or nothing when english is preferred or nothing matches.
+    pub fn lookup(&self, req: &HttpRequest, code: &str) -> Option<(&str, &str)> {
+        let accept_language = AcceptLanguage::parse(req).ok()?;
+
+        for preference in accept_language.ranked() {
+            // a wildcard accepts the fallback language
+            let Preference::Specific(tag) = preference else {
+                break;
+            };
+
+            let language = tag.primary_language().to_ascii_lowercase();
+            if language == FALLBACK_LANGUAGE {
+                break;
+            }
+
+            let translation = self
+                .languages
+                .get_key_value(&language)
+                .and_then(|(language, messages)| Some((language, messages.get(code)?)));
+            if let Some((language, message)) = translation {
+                return Some((language.as_str(), message.as_str()));
+            }
+        }
+
+        None
+    }
+}
+
+/// Replaces every `{name}` placeholder of the template by its value. The template is
+/// scanned once, so the values, which may come from the client, are never expanded.
+pub fn format(template: &str, args: &[(&str, &str)]) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let placeholder = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+            Some((*value, end + 1))
+        });
+        match placeholder {
+            Some((value, len)) => {
+                message.push_str(value);
+                rest = &rest[len..];
+            }
+            // unknown placeholders are kept as they are
+            None => {
+                message.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+
+    message
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Io(path, err) => write!(f, "failed to read {path}: {err}"),
+            CatalogError::Parse(path, err) => write!(f, "failed to parse {path}: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let args = [("platform", "linux_x86_64"), ("count", "2")];
+        for (template, expected) in [
+            ("no placeholder", "no placeholder"),
+            ("{platform}", "linux_x86_64"),
+            ("{count} for {platform}, {count}", "2 for linux_x86_64, 2"),
+            ("{unknown} {platform}", "{unknown} linux_x86_64"),
+            ("{{platform}}", "{linux_x86_64}"),
+            ("unclosed {platform", "unclosed {platform"),
+            ("é {platform} ü", "é linux_x86_64 ü"),
+        ] {
+            assert_eq!(format(template, &args), expected, "{template}");
+        }
+    }
+
+    #[test]
+    fn values_are_not_expanded() {
+        let args = [
+            ("error", "unknown variant `{valid_platforms}` {error}"),
+            ("valid_platforms", "windows_x64"),
+        ];
+        assert_eq!(
+            format("{error}, valid platforms are: {valid_platforms}", &args),
+            "unknown variant `{valid_platforms}` {error}, valid platforms are: windows_x64"
+        );
+    }
+}
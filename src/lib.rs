@@ -0,0 +1,712 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use actix_web::web::{self, Bytes};
+use futures::future::join_all;
+use game_data::{Asset, Assets, DownloadStats, GameRelease, PackRelease, Platform, ReleaseNote};
+use moka::future::Cache;
+use moka::Expiry;
+use semver::Version;
+
+use crate::asset_names::DEFAULT_FLAVOR;
+use crate::concurrency::InFlight;
+use crate::config::ApiConfig;
+use crate::downtime::Downtime;
+use crate::events::{Event, Events};
+use crate::fetcher::{Fetcher, FetcherError, PackConfig};
+use crate::i18n::{CatalogError, Catalogs};
+use crate::negotiation::Format;
+use crate::reporting::report_error;
+use crate::routes::TelemetryForwards;
+use crate::scheduler::Scheduler;
+use crate::service_accounts::{RateLimiter, ServiceAccount, ServiceAccounts};
+use crate::shared_cache::SharedCache;
+use crate::stats::DownloadStatsHistory;
+use crate::webhooks::ReleaseNotifier;
+
+pub mod asset_names;
+pub mod check;
+pub mod compression;
+pub mod concurrency;
+pub mod config;
+pub mod diagnostics;
+mod downtime;
+pub mod envelope;
+mod events;
+pub mod fetcher;
+pub mod game_data;
+mod github_client;
+pub mod i18n;
+pub mod logging;
+pub mod negotiation;
+pub mod payload_logging;
+pub mod reporting;
+mod routes;
+pub mod scheduler;
+pub mod secrets;
+mod service_accounts;
+mod shadow;
+mod shared_cache;
+mod stats;
+#[cfg(test)]
+mod testing;
+pub mod timeouts;
+pub mod trace_context;
+pub mod webhooks;
+
+pub use routes::configure_app;
+
+/// Longest time a serialized /game_version body is served, in seconds.
+const GAME_VERSION_RESPONSE_LIFESPAN: u64 = 60;
+/// Timeouts of the calls to the webhooks and the telemetry sink.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct AppData {
+    cache: Cache<String, CachedReleased>,
+    catalogs: Catalogs,
+    config: ApiConfig,
+    download_stats: DownloadStatsHistory,
+    fetcher: Fetcher,
+    /// Serialized /game_version bodies, emptied whenever what they are made of changes.
+    game_version_responses: Cache<(Platform, String, Format), Bytes>,
+    http_client: reqwest::Client,
+    in_flight: InFlight,
+    /// Last updater release fetched, served when fetching it again fails so an outage of
+    /// the updater repository doesn't take /game_version down.
+    last_updater_release: RwLock<Option<Arc<Assets>>>,
+    packs_cache: Cache<String, Arc<PackRelease>>,
+    /// Pin set from the admin API when there is no shared cache to store it in.
+    pinned_game_version: RwLock<Option<Version>>,
+    /// Downtime scheduled from the admin API when there is no shared cache to store it in.
+    downtime: RwLock<Option<Downtime>>,
+    /// Events set from the admin API when there is no shared cache to store them in.
+    events: RwLock<Events>,
+    release_notifier: ReleaseNotifier,
+    scheduler: Scheduler,
+    /// Accounts created from the admin API when there is no shared cache to store them in.
+    service_accounts: RwLock<ServiceAccounts>,
+    service_rate_limiter: RateLimiter,
+    /// Fetcher of the release source being validated, never served.
+    shadow_fetcher: Option<Fetcher>,
+    shared_cache: Option<SharedCache>,
+    telemetry_forwards: TelemetryForwards,
+}
+
+#[derive(Clone)]
+enum CachedReleased {
+    Updater(Arc<Assets>),
+    Game(Arc<GameRelease>),
+    ReleaseNotes(Arc<Vec<ReleaseNote>>),
+    Downtime(Option<Arc<Downtime>>),
+}
+
+/// Keeps the updater release for its own lifespan, every other entry for the cache one.
+struct ReleaseExpiry {
+    lifespan: Duration,
+    updater_lifespan: Duration,
+}
+
+impl Expiry<String, CachedReleased> for ReleaseExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &CachedReleased,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(match value {
+            CachedReleased::Updater(_) => self.updater_lifespan,
+            _ => self.lifespan,
+        })
+    }
+
+    // entries replaced by the refresh jobs start a new lifespan
+    fn expire_after_update(
+        &self,
+        key: &String,
+        value: &CachedReleased,
+        updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        self.expire_after_create(key, value, updated_at)
+    }
+}
+
+impl AppData {
+    /// Builds the application state from the configuration, connecting to the shared
+    /// cache when one is configured. The background jobs only run once [`AppData::start_jobs`]
+    /// is called.
+    pub async fn new(config: ApiConfig) -> Result<Self, InitError> {
+        let fetcher = Fetcher::from_config(&config).map_err(InitError::Fetcher)?;
+        let shadow_fetcher = Fetcher::shadow_from_config(&config)
+            .transpose()
+            .map_err(InitError::Fetcher)?;
+        let catalogs = Catalogs::load(&config.error_catalogs).map_err(InitError::Catalogs)?;
+
+        let mut scheduler = Scheduler::default();
+        scheduler.register("cache_refresh", config.cache_refresh_job, |app_data| {
+            Box::pin(async move { Ok(app_data.refresh_game_releases().await?) })
+        });
+        scheduler.register("updater_refresh", config.updater_refresh_job, |app_data| {
+            Box::pin(async move { Ok(app_data.refresh_updater_release().await?) })
+        });
+        scheduler.register("download_stats", config.download_stats_job, |app_data| {
+            Box::pin(async move { Ok(app_data.snapshot_download_stats().await?) })
+        });
+
+        let shared_cache = match &config.cache.redis_url {
+            Some(redis_url) => Some(
+                SharedCache::connect(
+                    redis_url,
+                    &config.cache.redis_key_prefix,
+                    config.cache.lifespan,
+                    config.cache.updater_lifespan,
+                )
+                .await
+                .map_err(InitError::SharedCache)?,
+            ),
+            None => None,
+        };
+
+        let http_client = reqwest::Client::builder()
+            .connect_timeout(HTTP_CONNECT_TIMEOUT)
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .map_err(InitError::HttpClient)?;
+        let release_notifier = ReleaseNotifier::from_config(&config, http_client.clone());
+
+        Ok(Self {
+            cache: Cache::builder()
+                .expire_after(ReleaseExpiry {
+                    lifespan: Duration::from_secs(config.cache.lifespan),
+                    updater_lifespan: Duration::from_secs(config.cache.updater_lifespan),
+                })
+                .build(),
+            catalogs,
+            download_stats: DownloadStatsHistory::new(config.cache.download_stats_history),
+            packs_cache: Cache::builder()
+                .time_to_live(Duration::from_secs(config.cache.lifespan))
+                .build(),
+            // the downtime notice depends on the time
+            game_version_responses: Cache::builder()
+                .time_to_live(Duration::from_secs(
+                    config.cache.lifespan.min(GAME_VERSION_RESPONSE_LIFESPAN),
+                ))
+                .build(),
+            config,
+            fetcher,
+            pinned_game_version: RwLock::new(None),
+            downtime: RwLock::new(None),
+            events: RwLock::new(Events::new()),
+            http_client,
+            in_flight: InFlight::default(),
+            last_updater_release: RwLock::new(None),
+            release_notifier,
+            scheduler,
+            service_accounts: RwLock::new(ServiceAccounts::new()),
+            service_rate_limiter: RateLimiter::default(),
+            shadow_fetcher,
+            shared_cache,
+            telemetry_forwards: TelemetryForwards::default(),
+        })
+    }
+
+    pub fn start_jobs(app_data: &web::Data<Self>) {
+        app_data.scheduler.start(app_data.clone());
+    }
+
+    async fn latest_updater_release(&self) -> Result<Arc<Assets>, Arc<FetcherError>> {
+        let cached = self
+            .cache
+            .try_get_with("latest_updater_release".to_string(), async {
+                let fetch = || async {
+                    let release = self.fetcher.get_latest_updater_release().await?;
+                    if let Some(shared_cache) = &self.shared_cache {
+                        shared_cache.set_updater_release(&release).await;
+                    }
+                    Ok::<_, FetcherError>(release)
+                };
+
+                let result = match &self.shared_cache {
+                    Some(shared_cache) => match shared_cache.get_updater_release().await {
+                        Some(release) => Ok(release),
+                        None => {
+                            shared_cache
+                                .single_flight(
+                                    "latest_updater_release",
+                                    || shared_cache.get_updater_release(),
+                                    fetch,
+                                )
+                                .await
+                        }
+                    },
+                    None => fetch().await,
+                };
+
+                let release = match result {
+                    Ok(release) => self.remember_updater_release(release),
+                    Err(err @ FetcherError::NoReleaseFound) => return Err(err),
+                    Err(err) => match self.last_updater_release.read().unwrap().clone() {
+                        Some(release) => {
+                            report_error(
+                                "failed to fetch the latest updater release, serving the last one",
+                                &err,
+                            );
+                            release
+                        }
+                        None => return Err(err),
+                    },
+                };
+                Ok(CachedReleased::Updater(release))
+            })
+            .await?;
+
+        match cached {
+            CachedReleased::Updater(updater_release) => Ok(updater_release),
+            _ => unreachable!("unexpected value cached as updater release"),
+        }
+    }
+
+    fn remember_updater_release(&self, release: Assets) -> Arc<Assets> {
+        let release = Arc::new(release);
+        *self.last_updater_release.write().unwrap() = Some(release.clone());
+        release
+    }
+
+    async fn latest_game_release(
+        &self,
+        flavor: &str,
+    ) -> Result<Arc<GameRelease>, Arc<FetcherError>> {
+        let cached = self
+            .cache
+            .try_get_with(game_release_key(flavor), async {
+                let fetch = || async {
+                    let pinned = self.pinned_game_version().await;
+                    let release = self
+                        .fetcher
+                        .get_latest_game_release(pinned.as_ref(), flavor)
+                        .await?;
+                    if let Some(shared_cache) = &self.shared_cache {
+                        shared_cache.set_game_release(flavor, &release).await;
+                    }
+                    Ok::<_, FetcherError>(release)
+                };
+
+                let release = match &self.shared_cache {
+                    Some(shared_cache) => match shared_cache.get_game_release(flavor).await {
+                        Some(release) => release,
+                        None => {
+                            shared_cache
+                                .single_flight(
+                                    &game_release_key(flavor),
+                                    || shared_cache.get_game_release(flavor),
+                                    fetch,
+                                )
+                                .await?
+                        }
+                    },
+                    None => fetch().await?,
+                };
+                Ok(CachedReleased::Game(Arc::new(release)))
+            })
+            .await?;
+
+        match cached {
+            CachedReleased::Game(game_release) => Ok(game_release),
+            _ => unreachable!("unexpected value cached as game release"),
+        }
+    }
+
+    /// Returns the version pinned from the admin API, falling back to the configured one.
+    async fn pinned_game_version(&self) -> Option<Version> {
+        let pinned = match &self.shared_cache {
+            Some(shared_cache) => shared_cache.get_pinned_game_version().await,
+            None => self.pinned_game_version.read().unwrap().clone(),
+        };
+
+        pinned.or_else(|| self.config.game.pinned_version.clone())
+    }
+
+    /// Returns the downtime scheduled from the admin API. It is read on every /game_version
+    /// request, so the shared one is cached like the releases: the other instances announce
+    /// a change within the cache lifespan.
+    async fn downtime(&self) -> Option<Arc<Downtime>> {
+        let Some(shared_cache) = &self.shared_cache else {
+            return self.downtime.read().unwrap().clone().map(Arc::new);
+        };
+
+        let cached = self
+            .cache
+            .get_with("downtime".to_string(), async {
+                CachedReleased::Downtime(shared_cache.get_downtime().await.map(Arc::new))
+            })
+            .await;
+
+        match cached {
+            CachedReleased::Downtime(downtime) => downtime,
+            _ => unreachable!("unexpected value cached as downtime"),
+        }
+    }
+
+    /// Schedules the downtime, replacing the previous one, or cancels it when None.
+    async fn set_downtime(&self, downtime: Option<Downtime>) {
+        let Some(shared_cache) = &self.shared_cache else {
+            *self.downtime.write().unwrap() = downtime;
+            self.game_version_responses.invalidate_all();
+            return;
+        };
+
+        shared_cache.set_downtime(downtime.as_ref()).await;
+        self.cache
+            .insert(
+                "downtime".to_string(),
+                CachedReleased::Downtime(downtime.map(Arc::new)),
+            )
+            .await;
+        self.game_version_responses.invalidate_all();
+    }
+
+    /// Returns the events set from the admin API, or None when the shared cache can't be read.
+    async fn events(&self) -> Option<Events> {
+        match &self.shared_cache {
+            Some(shared_cache) => shared_cache.get_events().await,
+            None => Some(self.events.read().unwrap().clone()),
+        }
+    }
+
+    /// Creates or replaces an event, or removes it when `event` is None, and returns whether
+    /// it existed. Returns None when the shared cache can't be read or written.
+    ///
+    /// Concurrent edits from several instances may overwrite each other, which is fine for
+    /// a handful of events edited by the team.
+    async fn set_event(&self, name: &str, event: Option<Event>) -> Option<bool> {
+        let Some(shared_cache) = &self.shared_cache else {
+            let mut events = self.events.write().unwrap();
+            let previous = match event {
+                Some(event) => events.insert(name.to_string(), event),
+                None => events.remove(name),
+            };
+            return Some(previous.is_some());
+        };
+
+        let mut events = shared_cache.get_events().await?;
+        let previous = match event {
+            Some(event) => events.insert(name.to_string(), event),
+            None => events.remove(name),
+        };
+        shared_cache
+            .set_events(&events)
+            .await
+            .then_some(previous.is_some())
+    }
+
+    /// Returns the service accounts, or None when the shared cache can't be read.
+    async fn service_accounts(&self) -> Option<ServiceAccounts> {
+        match &self.shared_cache {
+            Some(shared_cache) => shared_cache.get_service_accounts().await,
+            None => Some(self.service_accounts.read().unwrap().clone()),
+        }
+    }
+
+    /// Creates or replaces a service account, or removes it when `account` is None, and
+    /// returns whether it existed. Returns None when the shared cache can't be read or written.
+    async fn set_service_account(
+        &self,
+        name: &str,
+        account: Option<ServiceAccount>,
+    ) -> Option<bool> {
+        let Some(shared_cache) = &self.shared_cache else {
+            let mut accounts = self.service_accounts.write().unwrap();
+            let previous = match account {
+                Some(account) => accounts.insert(name.to_string(), account),
+                None => accounts.remove(name),
+            };
+            return Some(previous.is_some());
+        };
+
+        let mut accounts = shared_cache.get_service_accounts().await?;
+        let previous = match account {
+            Some(account) => accounts.insert(name.to_string(), account),
+            None => accounts.remove(name),
+        };
+        shared_cache
+            .set_service_accounts(&accounts)
+            .await
+            .then_some(previous.is_some())
+    }
+
+    /// Serves the release tagged with the version instead of the latest one, or removes
+    /// the pin set from the admin API. The release is fetched first, so a version without
+    /// a release is never pinned.
+    async fn pin_game_version(&self, version: Option<Version>) -> Result<(), FetcherError> {
+        let effective = version
+            .clone()
+            .or_else(|| self.config.game.pinned_version.clone());
+        let game_release = self
+            .fetcher
+            .get_latest_game_release(effective.as_ref(), DEFAULT_FLAVOR)
+            .await?;
+
+        match &self.shared_cache {
+            Some(shared_cache) => {
+                shared_cache.set_pinned_game_version(version.as_ref()).await;
+                shared_cache
+                    .set_game_release(DEFAULT_FLAVOR, &game_release)
+                    .await;
+            }
+            None => *self.pinned_game_version.write().unwrap() = version,
+        }
+
+        log::info!("now serving game version {}", game_release.version);
+        self.cache
+            .insert(
+                game_release_key(DEFAULT_FLAVOR),
+                CachedReleased::Game(Arc::new(game_release)),
+            )
+            .await;
+
+        // the other flavors are fetched again on their next request
+        for flavor in &self.config.game.flavors {
+            if flavor != DEFAULT_FLAVOR {
+                self.cache.invalidate(&game_release_key(flavor)).await;
+                if let Some(shared_cache) = &self.shared_cache {
+                    shared_cache.remove_game_release(flavor).await;
+                }
+            }
+        }
+        self.game_version_responses.invalidate_all();
+
+        Ok(())
+    }
+
+    async fn latest_pack_release(
+        &self,
+        pack: &PackConfig,
+    ) -> Result<Arc<PackRelease>, Arc<FetcherError>> {
+        self.packs_cache
+            .try_get_with(pack.name.clone(), async {
+                self.fetcher
+                    .get_latest_pack_release(pack)
+                    .await
+                    .map(Arc::new)
+            })
+            .await
+    }
+
+    /// Returns the asset of every content pack available for the platform, a pack which
+    /// can't be fetched is left out rather than failing the whole response.
+    async fn platform_packs(&self, platform: Platform) -> BTreeMap<String, Asset> {
+        let releases = join_all(
+            self.config
+                .packs
+                .iter()
+                .map(|pack| async move { (pack, self.latest_pack_release(pack).await) }),
+        )
+        .await;
+
+        let mut packs = BTreeMap::new();
+        for (pack, release) in releases {
+            let release = match release {
+                Ok(release) => release,
+                Err(err) => {
+                    report_error("failed to fetch a content pack release", &*err);
+                    continue;
+                }
+            };
+
+            let asset_name = pack.asset_name.replace("{platform}", platform.as_str());
+            match release.assets.get(&asset_name) {
+                Some(asset) => {
+                    packs.insert(pack.name.clone(), asset.clone());
+                }
+                None => log::warn!(
+                    "no asset {asset_name} in the latest release of pack {}",
+                    pack.name
+                ),
+            }
+        }
+
+        packs
+    }
+
+    /// Returns the recorded download statistics snapshots, oldest first, or None when the
+    /// shared cache can't be read.
+    async fn download_stats_history(&self) -> Option<Vec<Arc<DownloadStats>>> {
+        match &self.shared_cache {
+            Some(shared_cache) => shared_cache
+                .get_download_stats_history()
+                .await
+                .map(|snapshots| snapshots.into_iter().map(Arc::new).collect()),
+            None => Some(self.download_stats.snapshots()),
+        }
+    }
+
+    async fn latest_release_notes(&self) -> Result<Arc<Vec<ReleaseNote>>, Arc<FetcherError>> {
+        let cached = self
+            .cache
+            .try_get_with("release_notes".to_string(), async {
+                let notes = self.fetcher.get_game_release_notes().await?;
+                Ok(CachedReleased::ReleaseNotes(Arc::new(notes)))
+            })
+            .await?;
+
+        match cached {
+            CachedReleased::ReleaseNotes(notes) => Ok(notes),
+            _ => unreachable!("unexpected value cached as release notes"),
+        }
+    }
+
+    /// Fetches the latest game and pack releases and replaces the cached ones, so requests
+    /// don't have to wait on GitHub when the cache expires.
+    async fn refresh_game_releases(&self) -> Result<(), FetcherError> {
+        let pinned = self.pinned_game_version().await;
+        let game_release = self
+            .fetcher
+            .get_latest_game_release(pinned.as_ref(), DEFAULT_FLAVOR)
+            .await?;
+        self.release_notifier
+            .on_game_release(&game_release, self.shared_cache.as_ref())
+            .await;
+        if let Some(shared_cache) = &self.shared_cache {
+            shared_cache
+                .set_game_release(DEFAULT_FLAVOR, &game_release)
+                .await;
+        }
+
+        if let Some(shadow_fetcher) = &self.shadow_fetcher {
+            let mismatches = match shadow_fetcher
+                .get_latest_game_release(pinned.as_ref(), DEFAULT_FLAVOR)
+                .await
+            {
+                Ok(shadow_release) => shadow::diff_game_releases(&game_release, &shadow_release),
+                Err(err) => vec![format!("failed to fetch the game release: {err}")],
+            };
+            log_shadow_mismatches("game", &mismatches);
+        }
+
+        self.cache
+            .insert(
+                game_release_key(DEFAULT_FLAVOR),
+                CachedReleased::Game(Arc::new(game_release)),
+            )
+            .await;
+
+        for pack in &self.config.packs {
+            match self.fetcher.get_latest_pack_release(pack).await {
+                Ok(release) => {
+                    self.packs_cache
+                        .insert(pack.name.clone(), Arc::new(release))
+                        .await
+                }
+                Err(err) => report_error("failed to fetch a content pack release", &err),
+            }
+        }
+        self.game_version_responses.invalidate_all();
+
+        Ok(())
+    }
+
+    /// Same as [`AppData::refresh_game_releases`] for the updater, on its own schedule so
+    /// one failing doesn't keep the other from being refreshed.
+    async fn refresh_updater_release(&self) -> Result<(), FetcherError> {
+        let updater_release = self.fetcher.get_latest_updater_release().await?;
+        if let Some(shared_cache) = &self.shared_cache {
+            shared_cache.set_updater_release(&updater_release).await;
+        }
+
+        if let Some(shadow_fetcher) = &self.shadow_fetcher {
+            let mismatches = match shadow_fetcher.get_latest_updater_release().await {
+                Ok(shadow_release) => {
+                    shadow::diff_updater_releases(&updater_release, &shadow_release)
+                }
+                Err(err) => vec![format!("failed to fetch the updater release: {err}")],
+            };
+            log_shadow_mismatches("updater", &mismatches);
+        }
+
+        self.cache
+            .insert(
+                "latest_updater_release".to_string(),
+                CachedReleased::Updater(self.remember_updater_release(updater_release)),
+            )
+            .await;
+        self.game_version_responses.invalidate_all();
+
+        Ok(())
+    }
+
+    /// Records a download statistics snapshot in the history. The history is shared by the
+    /// instances sharing a cache, a single one of them records each snapshot.
+    async fn snapshot_download_stats(&self) -> Result<(), FetcherError> {
+        if let Some(shared_cache) = &self.shared_cache {
+            let interval = Duration::from_secs(self.config.download_stats_job.interval);
+            if !shared_cache
+                .claim("download_stats_snapshot", interval / 2)
+                .await
+            {
+                log::debug!("download statistics already recorded by another instance");
+                return Ok(());
+            }
+        }
+
+        let stats = self.fetcher.get_game_download_stats().await?;
+        match &self.shared_cache {
+            Some(shared_cache) => {
+                shared_cache
+                    .push_download_stats(&stats, self.config.cache.download_stats_history)
+                    .await
+            }
+            None => self.download_stats.record(Arc::new(stats)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Logs how a release fetched from the shadow source differs from the served one, its
+/// failures are only logged so they never affect the served releases.
+fn log_shadow_mismatches(release: &str, mismatches: &[String]) {
+    if mismatches.is_empty() {
+        log::debug!("shadow {release} release matches");
+        return;
+    }
+
+    log::warn!(
+        release = release,
+        mismatches = mismatches.len();
+        "shadow {release} release differs: {}",
+        mismatches.join(", ")
+    );
+}
+
+/// Key of the cached game release of a flavor, the default flavor keeps the key used
+/// before flavors existed.
+fn game_release_key(flavor: &str) -> String {
+    match flavor {
+        DEFAULT_FLAVOR => "latest_game_release".to_string(),
+        flavor => format!("latest_game_release_{flavor}"),
+    }
+}
+
+#[derive(Debug)]
+pub enum InitError {
+    Catalogs(CatalogError),
+    Fetcher(FetcherError),
+    HttpClient(reqwest::Error),
+    SharedCache(redis::RedisError),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::Catalogs(err) => write!(f, "failed to load the error catalogs: {err}"),
+            InitError::Fetcher(err) => write!(f, "failed to create the GitHub client: {err}"),
+            InitError::HttpClient(err) => write!(f, "failed to create the HTTP client: {err}"),
+            InitError::SharedCache(err) => write!(f, "failed to connect to redis: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for InitError {}
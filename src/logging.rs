@@ -0,0 +1,192 @@
+use std::io::{self, Write};
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use chrono::{SecondsFormat, Utc};
+use env_logger::fmt::Formatter;
+use log::kv::{self, Key, VisitSource};
+use log::Record;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::trace_context::{TraceContext, TRACEPARENT_HEADER};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Id of the current request, stored in the request extensions by [`log_requests`].
+#[derive(Clone)]
+pub struct RequestId(pub String);
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors such as Loki or ELK.
+    Json,
+}
+
+pub fn init(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if let LogFormat::Json = format {
+        builder.format(write_json);
+    }
+    builder.init();
+}
+
+fn write_json(buf: &mut Formatter, record: &Record) -> io::Result<()> {
+    let mut fields = Map::new();
+    fields.insert(
+        "timestamp".into(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    fields.insert("level".into(), record.level().as_str().into());
+    fields.insert("target".into(), record.target().into());
+    fields.insert("message".into(), record.args().to_string().into());
+
+    // visiting our own map can't fail
+    let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+
+    writeln!(buf, "{}", Value::Object(fields))
+}
+
+struct JsonFields<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_u64() {
+            value.into()
+        } else if let Some(value) = value.to_i64() {
+            value.into()
+        } else if let Some(value) = value.to_f64() {
+            value.into()
+        } else if let Some(value) = value.to_bool() {
+            value.into()
+        } else {
+            value.to_string().into()
+        };
+
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Tags every request with an id, taken from the `X-Request-Id` header when the client
+/// (or a reverse proxy) provides a sensible one, echoes it in the response headers and
+/// logs one line per request with its route, status and latency. The request is also
+/// served within the trace of its `traceparent` header, or a new one.
+pub async fn log_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 64 && id.bytes().all(|b| b.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let peer = req
+        .connection_info()
+        .realip_remote_addr()
+        .unwrap_or("-")
+        .to_string();
+
+    let trace_context = TraceContext::from_traceparent(
+        req.headers()
+            .get(TRACEPARENT_HEADER)
+            .and_then(|traceparent| traceparent.to_str().ok()),
+    );
+    let trace_id = trace_context.trace_id.clone();
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+    req.extensions_mut().insert(trace_context.clone());
+    let result = trace_context.scope(next.call(req)).await;
+
+    let (route, status) = match &result {
+        Ok(res) => (
+            res.request()
+                .match_pattern()
+                .unwrap_or_else(|| path.clone()),
+            res.status(),
+        ),
+        Err(err) => (path.clone(), err.as_response_error().status_code()),
+    };
+    let status = status.as_u16();
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    log::info!(
+        request_id = request_id.as_str(),
+        trace_id = trace_id.as_str(),
+        peer = peer.as_str(),
+        method = method.as_str(),
+        route = route.as_str(),
+        status = status,
+        latency_ms = latency_ms;
+        "{method} {path} {status}"
+    );
+
+    let mut res = result?;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::{web, App, HttpRequest};
+
+    use super::*;
+
+    /// The trace of the request, as stored in its extensions and as forwarded.
+    async fn traces(req: HttpRequest) -> String {
+        let stored = req.extensions().get::<TraceContext>().cloned().unwrap();
+        let current = TraceContext::current().unwrap();
+        assert_eq!(stored, current);
+        current.traceparent()
+    }
+
+    #[actix_web::test]
+    async fn requests_are_served_within_their_trace() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(log_requests))
+                .route("/", web::get().to(traces)),
+        )
+        .await;
+
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((
+                TRACEPARENT_HEADER,
+                format!("00-{trace_id}-00f067aa0ba902b7-01"),
+            ))
+            .to_request();
+        let traceparent = test::call_and_read_body(&app, req).await;
+        let traceparent = std::str::from_utf8(&traceparent).unwrap();
+        assert!(traceparent.starts_with(&format!("00-{trace_id}-")));
+        assert!(traceparent.ends_with("-01"));
+
+        let req = TestRequest::get().uri("/").to_request();
+        let traceparent = test::call_and_read_body(&app, req).await;
+        assert!(!std::str::from_utf8(&traceparent)
+            .unwrap()
+            .contains(trace_id));
+    }
+}
@@ -1,117 +1,109 @@
-use std::collections::HashMap;
-use std::sync::Mutex;
+use actix_web::middleware::{from_fn, Compress, Condition};
+use actix_web::{web, App, HttpServer};
+use clap::Parser;
+use this_api_of_mine::config::{self, CONFIG_PATH};
+use this_api_of_mine::{
+    check, compression, concurrency, configure_app, logging, payload_logging, reporting, timeouts,
+    AppData,
+};
 
-use actix_web::{get, middleware, web, App, HttpServer};
-use actix_web::{HttpResponse, Responder};
-use cached::{CachedAsync, TimedCache};
-use game_data::{Asset, GameRelease};
-use serde::Deserialize;
+use crate::cli::{Cli, Command};
 
-use crate::config::ApiConfig;
-use crate::fetcher::Fetcher;
-use crate::game_data::GameVersion;
+mod cli;
 
-mod config;
-mod fetcher;
-mod game_data;
-
-#[derive(Deserialize)]
-struct VersionQuery {
-    platform: String,
-}
-
-struct AppData {
-    cache: Mutex<TimedCache<&'static str, CachedReleased>>,
-    config: ApiConfig,
-    fetcher: Fetcher,
-}
-
-#[derive(Clone)]
-enum CachedReleased {
-    Updater(HashMap<String, Asset>),
-    Game(GameRelease),
+#[actix_web::main]
+async fn main() -> Result<(), std::io::Error> {
+    match Cli::parse().command() {
+        Command::Run => run_server().await,
+        Command::Check => {
+            let success = check::run().await;
+            std::process::exit(if success { 0 } else { 1 });
+        }
+        Command::CreateAdminToken => {
+            let token = hex::encode(rand::random::<[u8; 32]>());
+            println!("[security]\nadmin_token = \"{token}\"");
+            Ok(())
+        }
+    }
 }
 
-#[get("/game_version")]
-async fn game_version(
-    app_data: web::Data<AppData>,
-    ver_query: web::Query<VersionQuery>,
-) -> impl Responder {
-    let AppData {
-        cache,
-        config,
-        fetcher,
-    } = app_data.as_ref();
-    let mut cache = cache.lock().unwrap();
-
-    // TODO: remove .cloned
-    let Ok(CachedReleased::Updater(updater_release)) = cache
-        .try_get_or_set_with("latest_updater_release", || async {
-            fetcher
-                .get_latest_updater_release()
-                .await
-                .map(CachedReleased::Updater)
-        })
-        .await
-        .cloned()
-    else {
-        return HttpResponse::InternalServerError().finish();
-    };
-
-    // TODO: remove .cloned
-    let Ok(CachedReleased::Game(game_release)) = cache
-        .try_get_or_set_with("latest_game_release", || async {
-            fetcher
-                .get_latest_game_release()
-                .await
-                .map(CachedReleased::Game)
-        })
-        .await
-        .cloned()
-    else {
-        return HttpResponse::InternalServerError().finish();
+async fn run_server() -> Result<(), std::io::Error> {
+    let mut config = match config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{CONFIG_PATH}: {err}");
+            std::process::exit(1);
+        }
     };
 
-    let updater_filename = format!("{}_{}", ver_query.platform, config.updater_filename);
-
-    let (Some(updater), Some(binary)) = (updater_release.get(&updater_filename), game_release.binaries.get(&ver_query.platform)) else {
-        eprintln!(
-            "no updater or game binary release found for platform {}",
-            ver_query.platform
-        );
-        return HttpResponse::NotFound().finish();
+    let mut errors = config.load_secrets();
+    errors.extend(config.validate());
+    if !errors.is_empty() {
+        for error in errors {
+            eprintln!("{CONFIG_PATH}: {error}");
+        }
+        std::process::exit(1);
+    }
+
+    let tenants = match config.load_tenants() {
+        Ok(tenants) => tenants,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            std::process::exit(1);
+        }
     };
 
-    HttpResponse::Ok().json(web::Json(GameVersion {
-        assets: game_release.assets,
-        assets_version: game_release.assets_version.to_string(),
-        binaries: binary.clone(),
-        updater: updater.clone(),
-        version: game_release.version.to_string(),
-    }))
-}
-
-#[actix_web::main]
-async fn main() -> Result<(), std::io::Error> {
-    let config: ApiConfig = confy::load_path("tsom_api_config.toml").unwrap();
-    let fetcher = Fetcher::from_config(&config).unwrap();
-
     std::env::set_var("RUST_LOG", "info,actix_web=info");
-    env_logger::init();
-
-    let bind_address = format!("{}:{}", config.listen_address, config.listen_port);
-
-    let data_config = web::Data::new(AppData {
-        cache: Mutex::new(TimedCache::with_lifespan(config.cache_lifespan)), // 5min
-        config,
-        fetcher,
-    });
+    logging::init(config.server.log_format);
+    let _sentry = reporting::init(&config);
+
+    let bind_address = format!(
+        "{}:{}",
+        config.server.listen_address, config.server.listen_port
+    );
+    let compress = config.compression.enabled;
+    let log_payloads = config.payload_logging.enabled;
+
+    let data_config = web::Data::new(AppData::new(config).await.map_err(std::io::Error::other)?);
+    AppData::start_jobs(&data_config);
+
+    let mut tenants_data = Vec::new();
+    for (prefix, tenant_config) in tenants {
+        let data = AppData::new(tenant_config)
+            .await
+            .map_err(std::io::Error::other)?;
+        let data = web::Data::new(data);
+        AppData::start_jobs(&data);
+        tenants_data.push((format!("/{prefix}"), data));
+    }
 
     HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())
+            .wrap(from_fn(timeouts::limit_duration))
+            .wrap(from_fn(concurrency::limit_concurrency))
+            .wrap(Condition::new(
+                log_payloads,
+                from_fn(payload_logging::log_payloads),
+            ))
+            .wrap(Condition::new(
+                compress,
+                from_fn(compression::filter_responses),
+            ))
+            .wrap(Condition::new(compress, Compress::default()))
+            .wrap(from_fn(logging::log_requests))
             .app_data(data_config.clone())
-            .service(game_version)
+            .configure(|cfg| {
+                for (prefix, data) in &tenants_data {
+                    cfg.service(
+                        web::scope(prefix)
+                            .app_data(data.clone())
+                            .configure(configure_app),
+                    );
+                }
+            })
+            .configure(configure_app)
     })
     .bind(bind_address)?
     .run()
@@ -0,0 +1,77 @@
+use actix_web::http::header::{self, Accept, Header};
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use serde::Serialize;
+
+use crate::reporting::report_error;
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Serialization of a response body, picked from the `Accept` header of the request.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Json,
+    /// Smaller and cheaper to parse, for the game client.
+    MessagePack,
+}
+
+impl Format {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let Ok(accept) = Accept::parse(req) else {
+            return Format::Json;
+        };
+
+        for mime in accept.ranked() {
+            match mime.essence_str() {
+                "application/msgpack" | "application/x-msgpack" => return Format::MessagePack,
+                "application/json" | "application/*" | "*/*" => return Format::Json,
+                _ => {}
+            }
+        }
+
+        Format::Json
+    }
+}
+
+/// Finishes the response with the value serialized in the format the client asked for.
+pub fn respond<T: Serialize>(
+    req: &HttpRequest,
+    response: HttpResponseBuilder,
+    value: &T,
+) -> HttpResponse {
+    let format = Format::from_request(req);
+    match encode(format, value) {
+        Some(body) => respond_encoded(response, format, body),
+        None => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Serializes the value, the failures are reported.
+pub fn encode<T: Serialize>(format: Format, value: &T) -> Option<Bytes> {
+    let body = match format {
+        Format::Json => serde_json::to_vec(value)
+            .map_err(|err| report_error("failed to serialize a response", &err)),
+        // named fields keep the same shape as the JSON objects
+        Format::MessagePack => rmp_serde::to_vec_named(value)
+            .map_err(|err| report_error("failed to serialize a response", &err)),
+    };
+
+    body.ok().map(Bytes::from)
+}
+
+/// Finishes the response with a body already serialized in the format.
+pub fn respond_encoded(
+    mut response: HttpResponseBuilder,
+    format: Format,
+    body: Bytes,
+) -> HttpResponse {
+    let content_type = match format {
+        Format::Json => "application/json",
+        Format::MessagePack => MSGPACK_CONTENT_TYPE,
+    };
+
+    response
+        .append_header((header::VARY, "Accept"))
+        .content_type(content_type)
+        .body(body)
+}
@@ -0,0 +1,209 @@
+use actix_web::body::{self, BodySize, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::middleware::Next;
+use actix_web::web::{self, Bytes};
+use actix_web::{error, Error, HttpMessage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::logging::RequestId;
+use crate::timeouts::RouteGroup;
+use crate::AppData;
+
+const REDACTED: &str = "[redacted]";
+
+/// Logs the bodies of the requests and responses, to diagnose client integrations.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PayloadLogging {
+    pub enabled: bool,
+    /// Path prefixes of the logged routes, such as "/v2".
+    pub routes: Vec<String>,
+    /// The admin routes carry tokens and pins, they are skipped unless this is set.
+    pub include_admin: bool,
+    /// Bodies larger than this many bytes are only logged with their size.
+    pub max_body_size: usize,
+    /// JSON fields and query parameters whose values are replaced by "[redacted]",
+    /// names ending with "_<field>" are redacted as well.
+    pub redacted_fields: Vec<String>,
+}
+
+impl Default for PayloadLogging {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            routes: vec!["/".to_string()],
+            include_admin: false,
+            max_body_size: 4096,
+            redacted_fields: vec![
+                "token".to_string(),
+                "secret".to_string(),
+                "password".to_string(),
+                "pat".to_string(),
+                "signature".to_string(),
+                // service account keys are returned when the account is created
+                "key".to_string(),
+            ],
+        }
+    }
+}
+
+fn is_logged(app_data: &AppData, path: &str) -> bool {
+    let config = &app_data.config.payload_logging;
+    if !config.include_admin && RouteGroup::of(path, &app_data.config.tenants) == RouteGroup::Admin
+    {
+        return false;
+    }
+
+    config.routes.iter().any(|route| path.starts_with(route))
+}
+
+impl PayloadLogging {
+    fn is_redacted(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.redacted_fields.iter().any(|field| {
+            let field = field.to_ascii_lowercase();
+            name == field || name.ends_with(&format!("_{field}"))
+        })
+    }
+
+    fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (name, value) in fields {
+                    if self.is_redacted(name) {
+                        *value = REDACTED.into();
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact_json(value)),
+            _ => {}
+        }
+    }
+
+    fn redact_query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.is_redacted(name) => format!("{name}={REDACTED}"),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Only JSON bodies are logged since they are the only ones which can be redacted.
+    fn describe(&self, headers: &HeaderMap, bytes: &[u8]) -> String {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("no content type");
+
+        if bytes.is_empty() {
+            return "(empty)".to_string();
+        }
+
+        let is_json = content_type
+            .split(';')
+            .next()
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"));
+        match serde_json::from_slice::<Value>(bytes) {
+            Ok(mut value) if is_json => {
+                self.redact_json(&mut value);
+                value.to_string()
+            }
+            _ => format!("({} bytes of {content_type})", bytes.len()),
+        }
+    }
+}
+
+/// Logs the bodies of the requests and responses of the configured routes, with their
+/// tokens redacted. Has to be wrapped inside the compression so the responses are readable.
+pub async fn log_payloads(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let app_data = req
+        .app_data::<web::Data<AppData>>()
+        .cloned()
+        .expect("missing app data");
+    if !is_logged(&app_data, req.path()) {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_boxed_body);
+    }
+
+    let config = &app_data.config.payload_logging;
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default();
+    let target = match req.query_string() {
+        "" => req.path().to_string(),
+        query => format!("{}?{}", req.path(), config.redact_query(query)),
+    };
+    let method = req.method().clone();
+
+    // the body has to be buffered to be logged, so large or unsized ones are left alone
+    let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    let request_body = match content_length {
+        Some(length) if length <= config.max_body_size => {
+            let bytes = req.extract::<Bytes>().await?;
+            let description = config.describe(req.headers(), &bytes);
+            req.set_payload(Payload::from(bytes));
+            description
+        }
+        Some(length) => format!("({length} bytes, not logged)"),
+        None if req.headers().contains_key(header::TRANSFER_ENCODING) => {
+            "(unsized, not logged)".to_string()
+        }
+        None => "(empty)".to_string(),
+    };
+
+    log::info!(
+        request_id = request_id.as_str();
+        "{method} {target} request body: {request_body}"
+    );
+
+    let res = next.call(req).await?;
+    let status = res.status().as_u16();
+
+    let (res, response_body) = match res.response().body().size() {
+        BodySize::Sized(size) if size as usize <= config.max_body_size => {
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let bytes = body::to_bytes(body)
+                .await
+                .map_err(|err| error::ErrorInternalServerError(err.into()))?;
+            let description = config.describe(res.headers(), &bytes);
+            let res = ServiceResponse::new(req, res.set_body(BoxBody::new(bytes)));
+            (res, description)
+        }
+        BodySize::Sized(size) => (
+            res.map_into_boxed_body(),
+            format!("({size} bytes, not logged)"),
+        ),
+        BodySize::None => (res.map_into_boxed_body(), "(empty)".to_string()),
+        BodySize::Stream => (
+            res.map_into_boxed_body(),
+            "(streamed, not logged)".to_string(),
+        ),
+    };
+
+    log::info!(
+        request_id = request_id.as_str(),
+        status = status;
+        "{method} {target} response body: {response_body}"
+    );
+
+    Ok(res)
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::Mutex;
+
+use crate::config::ApiConfig;
+
+/// Number of errors reported since startup, by context.
+static ERROR_COUNTS: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+
+/// Sets up the forwarding of reported errors to Sentry when a DSN is configured, the
+/// returned guard flushes pending events when dropped.
+pub fn init(config: &ApiConfig) -> Option<sentry::ClientInitGuard> {
+    let dsn = config.server.sentry_dsn.as_deref()?;
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            ..Default::default()
+        },
+    ));
+
+    guard.is_enabled().then_some(guard)
+}
+
+/// Reports an error which can't be returned to anyone, such as a failing background job
+/// or an upstream failure hidden behind a 500: it's logged, counted and sent to Sentry.
+pub fn report_error<E: Error + ?Sized>(context: &'static str, err: &E) {
+    log::error!(context = context; "{context}: {err}");
+
+    *ERROR_COUNTS.lock().unwrap().entry(context).or_default() += 1;
+
+    sentry::with_scope(
+        |scope| scope.set_tag("context", context),
+        || sentry::capture_error(err),
+    );
+}
+
+pub fn error_counts() -> BTreeMap<&'static str, u64> {
+    ERROR_COUNTS.lock().unwrap().clone()
+}
@@ -0,0 +1,562 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::{
+    ErrorForbidden, ErrorNotFound, ErrorServiceUnavailable, ErrorUnauthorized, InternalError,
+};
+use actix_web::http::header;
+use actix_web::middleware::{from_fn, Next};
+use actix_web::{delete, get, post, put, web, Error, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use secure_string::SecureString;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::downtime::Downtime;
+use crate::events::{Event, NamedEvent};
+use crate::fetcher::FetcherError;
+use crate::game_data::DownloadStats;
+use crate::reporting::{self, report_error};
+use crate::service_accounts::{self, ServiceAccount};
+use crate::AppData;
+
+#[derive(Deserialize)]
+struct PinRequest {
+    version: Version,
+}
+
+#[derive(Serialize)]
+struct PinnedRelease {
+    pinned: Option<Version>,
+}
+
+#[derive(Serialize)]
+struct ScheduledDowntime {
+    downtime: Option<Downtime>,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountRequest {
+    routes: Vec<String>,
+    #[serde(default)]
+    rate_limit: u32,
+}
+
+/// Service account as listed, without the hash of its key.
+#[derive(Serialize)]
+struct ServiceAccountInfo<'a> {
+    name: &'a str,
+    routes: &'a [String],
+    rate_limit: u32,
+    created_at: DateTime<Utc>,
+    /// Only returned when the account is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<&'a str>,
+}
+
+impl<'a> ServiceAccountInfo<'a> {
+    fn new(name: &'a str, account: &'a ServiceAccount) -> Self {
+        Self {
+            name,
+            routes: &account.routes,
+            rate_limit: account.rate_limit,
+            created_at: account.created_at,
+            key: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EventList<'a> {
+    events: Vec<NamedEvent<'a>>,
+}
+
+#[derive(Serialize)]
+struct DownloadStatsReport<'a> {
+    snapshots: Vec<&'a DownloadStats>,
+}
+
+/// Routes reserved to the team, only reachable with the configured `admin_token`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    // registered before the scope, the page holds no data and asks for the token itself
+    #[cfg(feature = "admin-ui")]
+    cfg.service(web::resource("/admin/ui").get(admin_ui));
+
+    cfg.service(
+        web::scope("/admin")
+            .wrap(from_fn(require_admin_token))
+            .service(download_stats)
+            .service(jobs)
+            .service(errors)
+            .service(telemetry)
+            .service(pinned_release)
+            .service(pin_release)
+            .service(unpin_release)
+            .service(validate_releases)
+            .service(scheduled_downtime)
+            .service(schedule_downtime)
+            .service(cancel_downtime)
+            .service(list_service_accounts)
+            .service(create_service_account)
+            .service(delete_service_account)
+            .service(list_events)
+            .service(put_event)
+            .service(delete_event),
+    );
+}
+
+#[cfg(feature = "admin-ui")]
+async fn admin_ui() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header((
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; connect-src 'self'; script-src 'unsafe-inline'; \
+             style-src 'unsafe-inline'; frame-ancestors 'none'",
+        ))
+        .body(include_str!("admin_ui.html"))
+}
+
+/// Lets the requests with the admin token through, or with the key of a service account
+/// allowed to reach the route and within its rate limit. The others are answered rather
+/// than failed, so the `/v2` envelope wraps them as well.
+async fn require_admin_token(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    match check_admin_token(&req).await {
+        Ok(()) => next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body),
+        Err(err) => Ok(req.error_response(err).map_into_right_body()),
+    }
+}
+
+async fn check_admin_token(req: &ServiceRequest) -> Result<(), Error> {
+    let app_data = req
+        .app_data::<web::Data<AppData>>()
+        .cloned()
+        .expect("missing app data");
+
+    // the admin API doesn't exist as far as clients know when no token is configured
+    let Some(admin_token) = &app_data.config.security.admin_token else {
+        return Err(ErrorNotFound("not found"));
+    };
+
+    let Some(token) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return Err(ErrorUnauthorized("invalid admin token"));
+    };
+
+    if SecureString::from(token) == *admin_token {
+        return Ok(());
+    }
+
+    let Some(accounts) = app_data.service_accounts().await else {
+        return Err(ErrorServiceUnavailable("service accounts unavailable"));
+    };
+    let Some((name, account)) = service_accounts::find(&accounts, token) else {
+        return Err(ErrorUnauthorized("invalid admin token"));
+    };
+
+    // the path of the route within the /admin scope
+    if !account.allows(req.method(), req.match_info().unprocessed()) {
+        return Err(ErrorForbidden("route not allowed for this service account"));
+    }
+
+    if let Err(retry_after) = app_data
+        .service_rate_limiter
+        .check(name, account.rate_limit)
+    {
+        let response = HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, retry_after))
+            .finish();
+        return Err(InternalError::from_response("rate limited", response).into());
+    }
+
+    Ok(())
+}
+
+/// Snapshots recorded by the download_stats job, oldest first.
+#[get("/stats/downloads")]
+async fn download_stats(app_data: web::Data<AppData>) -> impl Responder {
+    let Some(snapshots) = app_data.download_stats_history().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+
+    HttpResponse::Ok().json(DownloadStatsReport {
+        snapshots: snapshots.iter().map(AsRef::as_ref).collect(),
+    })
+}
+
+#[get("/jobs")]
+async fn jobs(app_data: web::Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(app_data.scheduler.report())
+}
+
+#[get("/errors")]
+async fn errors() -> impl Responder {
+    HttpResponse::Ok().json(reporting::error_counts())
+}
+
+#[get("/telemetry")]
+async fn telemetry(app_data: web::Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(app_data.telemetry_forwards.report())
+}
+
+#[get("/releases/pin")]
+async fn pinned_release(app_data: web::Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(PinnedRelease {
+        pinned: app_data.pinned_game_version().await,
+    })
+}
+
+#[put("/releases/pin")]
+async fn pin_release(
+    app_data: web::Data<AppData>,
+    request: web::Json<PinRequest>,
+) -> impl Responder {
+    let PinRequest { version } = request.into_inner();
+    match app_data.pin_game_version(Some(version.clone())).await {
+        Ok(()) => HttpResponse::Ok().json(PinnedRelease {
+            pinned: Some(version),
+        }),
+        Err(err @ FetcherError::PinnedReleaseNotFound(_)) => {
+            HttpResponse::NotFound().body(err.to_string())
+        }
+        Err(err) => {
+            report_error("failed to pin a game release", &err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Removes the pin set from the admin API, the configured one applies again.
+#[delete("/releases/pin")]
+async fn unpin_release(app_data: web::Data<AppData>) -> impl Responder {
+    match app_data.pin_game_version(None).await {
+        Ok(()) => HttpResponse::Ok().json(PinnedRelease {
+            pinned: app_data.pinned_game_version().await,
+        }),
+        Err(err) => {
+            report_error("failed to unpin the game release", &err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[get("/releases/validate")]
+async fn validate_releases(app_data: web::Data<AppData>) -> impl Responder {
+    match app_data.fetcher.validate_releases().await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => {
+            report_error("failed to validate the releases", &err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[get("/downtime")]
+async fn scheduled_downtime(app_data: web::Data<AppData>) -> impl Responder {
+    HttpResponse::Ok().json(ScheduledDowntime {
+        downtime: app_data.downtime().await.as_deref().cloned(),
+    })
+}
+
+/// Schedules a downtime, replacing the one already scheduled.
+#[put("/downtime")]
+async fn schedule_downtime(
+    app_data: web::Data<AppData>,
+    downtime: web::Json<Downtime>,
+) -> impl Responder {
+    let downtime = downtime.into_inner();
+    if let Err(err) = downtime.validate() {
+        return HttpResponse::BadRequest().body(err);
+    }
+
+    log::info!(
+        "downtime scheduled from {} to {}",
+        downtime.starts_at,
+        downtime.ends_at
+    );
+    app_data.set_downtime(Some(downtime.clone())).await;
+    HttpResponse::Ok().json(ScheduledDowntime {
+        downtime: Some(downtime),
+    })
+}
+
+#[delete("/downtime")]
+async fn cancel_downtime(app_data: web::Data<AppData>) -> impl Responder {
+    app_data.set_downtime(None).await;
+    HttpResponse::Ok().json(ScheduledDowntime { downtime: None })
+}
+
+#[get("/service_accounts")]
+async fn list_service_accounts(app_data: web::Data<AppData>) -> impl Responder {
+    let Some(accounts) = app_data.service_accounts().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+
+    HttpResponse::Ok().json(
+        accounts
+            .iter()
+            .map(|(name, account)| ServiceAccountInfo::new(name, account))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Creates the account with a new key, replacing the one with the same name along with
+/// its key. The key is only returned in this response.
+#[post("/service_accounts/{name}")]
+async fn create_service_account(
+    app_data: web::Data<AppData>,
+    name: web::Path<String>,
+    request: web::Json<ServiceAccountRequest>,
+) -> impl Responder {
+    let ServiceAccountRequest { routes, rate_limit } = request.into_inner();
+    if let Err(err) = service_accounts::validate_routes(&routes) {
+        return HttpResponse::BadRequest().body(err);
+    }
+
+    let (account, key) = ServiceAccount::generate(routes, rate_limit);
+    match app_data
+        .set_service_account(&name, Some(account.clone()))
+        .await
+    {
+        Some(_) => {
+            log::info!("service account {name} created");
+            HttpResponse::Created().json(ServiceAccountInfo {
+                key: Some(&key),
+                ..ServiceAccountInfo::new(&name, &account)
+            })
+        }
+        None => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[delete("/service_accounts/{name}")]
+async fn delete_service_account(
+    app_data: web::Data<AppData>,
+    name: web::Path<String>,
+) -> impl Responder {
+    match app_data.set_service_account(&name, None).await {
+        Some(true) => {
+            log::info!("service account {name} deleted");
+            HttpResponse::NoContent().finish()
+        }
+        Some(false) => HttpResponse::NotFound().finish(),
+        None => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[get("/events")]
+async fn list_events(app_data: web::Data<AppData>) -> impl Responder {
+    let Some(events) = app_data.events().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+
+    HttpResponse::Ok().json(EventList {
+        events: events
+            .iter()
+            .map(|(name, event)| NamedEvent { name, event })
+            .collect(),
+    })
+}
+
+/// Creates the event, or replaces the one with the same name.
+#[put("/events/{name}")]
+async fn put_event(
+    app_data: web::Data<AppData>,
+    name: web::Path<String>,
+    event: web::Json<Event>,
+) -> impl Responder {
+    let event = event.into_inner();
+    if let Err(err) = event.validate(&name) {
+        return HttpResponse::BadRequest().body(err);
+    }
+
+    match app_data.set_event(&name, Some(event.clone())).await {
+        Some(existed) => {
+            let response = NamedEvent {
+                name: &name,
+                event: &event,
+            };
+            if existed {
+                HttpResponse::Ok().json(response)
+            } else {
+                HttpResponse::Created().json(response)
+            }
+        }
+        None => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[delete("/events/{name}")]
+async fn delete_event(app_data: web::Data<AppData>, name: web::Path<String>) -> impl Responder {
+    match app_data.set_event(&name, None).await {
+        Some(true) => HttpResponse::NoContent().finish(),
+        Some(false) => HttpResponse::NotFound().finish(),
+        None => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use actix_web::dev::{Service, ServiceResponse};
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+    use chrono::Utc;
+
+    use crate::config::ApiConfig;
+    use crate::configure_app;
+    use crate::game_data::DownloadStats;
+    use crate::testing::{self, ADMIN_TOKEN};
+
+    /// Status of a request, the errors of the middleware included.
+    async fn status<S, R>(app: &S, req: R) -> u16
+    where
+        S: Service<R, Response = ServiceResponse, Error = actix_web::Error>,
+    {
+        match test::try_call_service(app, req).await {
+            Ok(res) => res.status().as_u16(),
+            Err(err) => err.as_response_error().status_code().as_u16(),
+        }
+    }
+
+    fn admin_get(uri: &str) -> TestRequest {
+        TestRequest::get()
+            .uri(uri)
+            .insert_header(("Authorization", format!("Bearer {ADMIN_TOKEN}")))
+    }
+
+    /// Only the job records snapshots, reading them doesn't fetch new ones.
+    #[actix_web::test]
+    async fn download_stats_are_only_recorded_by_the_job() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .configure(configure_app),
+        )
+        .await;
+
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, admin_get("/admin/stats/downloads").to_request())
+                .await;
+        assert_eq!(body["snapshots"], serde_json::json!([]));
+
+        for total in [10, 25] {
+            app_data.download_stats.record(Arc::new(DownloadStats {
+                timestamp: Utc::now(),
+                total,
+                versions: BTreeMap::new(),
+            }));
+        }
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, admin_get("/admin/stats/downloads").to_request())
+                .await;
+        let totals = body["snapshots"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|snapshot| snapshot["total"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(totals, [10, 25]);
+    }
+
+    #[actix_web::test]
+    async fn admin_routes_require_the_token() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        assert_eq!(
+            status(&app, TestRequest::get().uri("/admin/jobs").to_request()).await,
+            401
+        );
+        let wrong_token = TestRequest::get()
+            .uri("/admin/jobs")
+            .insert_header(("Authorization", "Bearer not_the_admin_token"));
+        assert_eq!(status(&app, wrong_token.to_request()).await, 401);
+        assert_eq!(
+            status(&app, admin_get("/admin/jobs").to_request()).await,
+            200
+        );
+
+        // without a configured token, the admin routes don't exist
+        let app_data = testing::app_data(ApiConfig::default()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+        assert_eq!(
+            status(&app, admin_get("/admin/jobs").to_request()).await,
+            404
+        );
+    }
+
+    #[actix_web::test]
+    async fn service_accounts_only_reach_their_routes() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let create = |routes: serde_json::Value| {
+            TestRequest::post()
+                .uri("/admin/service_accounts/stats_widget")
+                .insert_header(("Authorization", format!("Bearer {ADMIN_TOKEN}")))
+                .set_json(serde_json::json!({ "routes": routes }))
+        };
+        assert_eq!(
+            status(&app, create(serde_json::json!(["/jobs"])).to_request()).await,
+            400
+        );
+        assert_eq!(
+            status(
+                &app,
+                create(serde_json::json!(["GET /service_accounts"])).to_request()
+            )
+            .await,
+            400
+        );
+
+        let res =
+            test::call_service(&app, create(serde_json::json!(["GET /jobs"])).to_request()).await;
+        assert_eq!(res.status(), 201);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        let key = body["key"].as_str().unwrap().to_string();
+
+        let with_key =
+            |req: TestRequest| req.insert_header(("Authorization", format!("Bearer {key}")));
+        assert_eq!(
+            status(
+                &app,
+                with_key(TestRequest::get().uri("/admin/jobs")).to_request()
+            )
+            .await,
+            200
+        );
+        assert_eq!(
+            status(
+                &app,
+                with_key(TestRequest::get().uri("/admin/errors")).to_request()
+            )
+            .await,
+            403
+        );
+        let pin = TestRequest::put()
+            .uri("/admin/releases/pin")
+            .set_json(serde_json::json!({ "version": "0.5.0" }));
+        assert_eq!(status(&app, with_key(pin).to_request()).await, 403);
+        assert_eq!(
+            status(
+                &app,
+                with_key(TestRequest::get().uri("/admin/service_accounts")).to_request()
+            )
+            .await,
+            403
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::events::{self, NamedEvent};
+use crate::AppData;
+
+#[derive(Serialize)]
+struct ActiveEvents<'a> {
+    events: Vec<NamedEvent<'a>>,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(active_events);
+}
+
+/// Lists the events running now. The response can be cached until the next event starts or
+/// ends, and at most for the cache lifespan so edits from the admin API show up.
+#[get("/events/active")]
+async fn active_events(app_data: web::Data<AppData>) -> impl Responder {
+    let Some(events) = app_data.events().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+
+    let now = Utc::now();
+    let max_age = match events::next_change(&events, now) {
+        Some(next_change) => (next_change - now)
+            .num_seconds()
+            .clamp(0, app_data.config.cache.lifespan as i64) as u32,
+        None => app_data.config.cache.lifespan as u32,
+    };
+
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(max_age),
+        ]))
+        .json(ActiveEvents {
+            events: events::active(&events, now),
+        })
+}
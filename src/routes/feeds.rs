@@ -0,0 +1,140 @@
+use std::fmt::{self, Write};
+
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::{SecondsFormat, Utc};
+
+use crate::config::ApiConfig;
+use crate::game_data::ReleaseNote;
+use crate::reporting::report_error;
+use crate::AppData;
+
+const MAX_ENTRIES: usize = 20;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(releases);
+}
+
+/// Atom feed of the game releases, for feed readers and community sites.
+#[get("/feeds/releases.xml")]
+async fn releases(app_data: web::Data<AppData>) -> impl Responder {
+    let notes = match app_data.latest_release_notes().await {
+        Ok(notes) => notes,
+        Err(err) => {
+            report_error("failed to fetch the release notes", &*err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let feed = render_atom(&app_data.config, &notes).expect("writing to a String can't fail");
+    HttpResponse::Ok()
+        .content_type("application/atom+xml; charset=utf-8")
+        .body(feed)
+}
+
+fn render_atom(config: &ApiConfig, notes: &[ReleaseNote]) -> Result<String, fmt::Error> {
+    let releases_url = format!(
+        "https://github.com/{}/{}/releases",
+        config.github.repo_owner, config.github.game_repository
+    );
+    let updated = notes
+        .first()
+        .map_or_else(Utc::now, |note| note.published_at)
+        .to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut feed = String::new();
+    feed.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    writeln!(feed, "  <id>{}</id>", escape(&releases_url))?;
+    writeln!(
+        feed,
+        "  <title>{} releases</title>",
+        escape(&config.github.game_repository)
+    )?;
+    writeln!(feed, "  <updated>{updated}</updated>")?;
+    writeln!(
+        feed,
+        "  <link rel=\"alternate\" href=\"{}\"/>",
+        escape(&releases_url)
+    )?;
+    writeln!(
+        feed,
+        "  <author><name>{}</name></author>",
+        escape(&config.github.repo_owner)
+    )?;
+
+    for note in notes.iter().take(MAX_ENTRIES) {
+        let published = note.published_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        feed.push_str("  <entry>\n");
+        writeln!(feed, "    <id>{}</id>", escape(&note.url))?;
+        writeln!(feed, "    <title>{}</title>", escape(&note.title))?;
+        writeln!(feed, "    <updated>{published}</updated>")?;
+        writeln!(feed, "    <published>{published}</published>")?;
+        writeln!(
+            feed,
+            "    <link rel=\"alternate\" href=\"{}\"/>",
+            escape(&note.url)
+        )?;
+        writeln!(
+            feed,
+            "    <category term=\"{}\"/>",
+            escape(&note.version.to_string())
+        )?;
+        if let Some(body) = &note.body {
+            writeln!(
+                feed,
+                "    <content type=\"text\">{}</content>",
+                escape(body)
+            )?;
+        }
+        feed.push_str("  </entry>\n");
+    }
+
+    feed.push_str("</feed>\n");
+    Ok(feed)
+}
+
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // characters XML 1.0 can't represent, even escaped
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use crate::configure_app;
+    use crate::testing::{self, GAME_VERSION};
+
+    #[actix_web::test]
+    async fn releases_feed_lists_the_release_notes() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let req = TestRequest::get().uri("/feeds/releases.xml").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "application/atom+xml; charset=utf-8"
+        );
+
+        let feed = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert_eq!(feed.matches("<entry>").count(), 1);
+        assert!(feed.contains(&format!("ThisSpaceOfMine {GAME_VERSION}")));
+        assert!(feed.contains(&format!("https://github.invalid/releases/{GAME_VERSION}")));
+    }
+}
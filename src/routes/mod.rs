@@ -0,0 +1,94 @@
+use actix_web::web;
+
+mod admin;
+mod events;
+mod feeds;
+mod status;
+mod telemetry;
+mod v2;
+mod version;
+
+pub(crate) use telemetry::TelemetryForwards;
+
+/// First segment of the paths of every route registered by [`configure_app`], which a
+/// tenant can't be named after without shadowing them.
+pub(crate) const ROOT_SEGMENTS: [&str; 7] = [
+    "admin",
+    "feeds",
+    "game_version",
+    "status",
+    "updater_version",
+    "v1",
+    "v2",
+];
+
+/// Registers every route of the API, the [`AppData`](crate::AppData) has to be provided
+/// by the caller with `App::app_data`. New routes are added here, so the binary and
+/// embedders always serve the same API.
+pub fn configure_app(cfg: &mut web::ServiceConfig) {
+    cfg.service(version::game_version)
+        .service(version::updater_version)
+        .configure(v2::configure)
+        .configure(feeds::configure)
+        .configure(status::configure)
+        .service(
+            web::scope("/v1")
+                .configure(events::configure)
+                .configure(telemetry::configure),
+        )
+        .configure(admin::configure);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::testing;
+
+    /// A route of each root segment, the test fails when one is added without a probe.
+    const PROBES: [&str; 8] = [
+        "/admin/jobs",
+        "/feeds/releases.xml",
+        "/game_version",
+        "/status",
+        "/updater_version",
+        "/v1/events/active",
+        "/v1/telemetry",
+        "/v2/game_version",
+    ];
+
+    #[actix_web::test]
+    async fn root_segments_match_the_registered_routes() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let mut roots = Vec::new();
+        for probe in PROBES {
+            let req = TestRequest::get()
+                .uri(probe)
+                .insert_header(("Authorization", format!("Bearer {}", testing::ADMIN_TOKEN)))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            let pattern = res
+                .request()
+                .match_pattern()
+                .unwrap_or_else(|| panic!("{probe} isn't routed"));
+            let root = pattern.split('/').nth(1).unwrap().to_string();
+            assert!(
+                ROOT_SEGMENTS.contains(&root.as_str()),
+                "{root} is missing from ROOT_SEGMENTS"
+            );
+            roots.push(root);
+        }
+        for root in ROOT_SEGMENTS {
+            assert!(roots.iter().any(|r| r == root), "{root} has no route");
+        }
+
+        for unrouted in ["/other_game/game_version", "/statuses", "/v3/game_version"] {
+            let res = test::call_service(&app, TestRequest::get().uri(unrouted).to_request()).await;
+            assert_eq!(res.request().match_pattern(), None, "{unrouted}");
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt::{self, Write};
+
+use actix_web::http::header::{self, Accept, CacheControl, CacheDirective, Header};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::feeds::escape;
+use crate::asset_names::DEFAULT_FLAVOR;
+use crate::downtime::DowntimeNotice;
+use crate::{negotiation, AppData};
+
+/// Seconds the status can be cached for, it is meant to be linked from the website.
+const MAX_AGE: u32 = 30;
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum State {
+    Ok,
+    /// The releases can't be fetched, or haven't been refreshed for a while.
+    Degraded,
+    /// A scheduled downtime is in progress.
+    Maintenance,
+}
+
+#[derive(Serialize)]
+struct Status {
+    state: State,
+    game_version: Option<String>,
+    last_release_refresh: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    downtime: Option<DowntimeNotice>,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(status_page);
+}
+
+/// Summary of the health of the API, as JSON or as an HTML page for browsers.
+#[get("/status")]
+async fn status_page(req: HttpRequest, app_data: web::Data<AppData>) -> impl Responder {
+    let status = app_data.status().await;
+
+    let mut response = HttpResponse::Ok();
+    response.insert_header(CacheControl(vec![
+        CacheDirective::Public,
+        CacheDirective::MaxAge(MAX_AGE),
+    ]));
+
+    if prefers_html(&req) {
+        let html = render_html(&app_data.config.github.game_repository, &status)
+            .expect("writing to a String can't fail");
+        return response
+            .append_header((header::VARY, "Accept"))
+            .content_type("text/html; charset=utf-8")
+            .body(html);
+    }
+
+    negotiation::respond(&req, response, &status)
+}
+
+impl AppData {
+    async fn status(&self) -> Status {
+        let now = Utc::now();
+        let game_version = self
+            .latest_game_release(DEFAULT_FLAVOR)
+            .await
+            .ok()
+            .map(|release| release.version.to_string());
+        let downtime = self
+            .downtime()
+            .await
+            .and_then(|downtime| downtime.notice(now));
+
+        let last_release_refresh = self.scheduler.last_success("cache_refresh");
+        // a few failed refreshes are fine as long as the releases stay reachable
+        let refresh_interval = self.config.cache_refresh_job.interval as i64;
+        let refresh_late = refresh_interval > 0
+            && last_release_refresh.is_some_and(|last_refresh| {
+                (now - last_refresh).num_seconds() > 3 * refresh_interval
+            });
+
+        let state = if downtime
+            .as_ref()
+            .is_some_and(|downtime| downtime.starts_at <= now)
+        {
+            State::Maintenance
+        } else if game_version.is_none() || refresh_late {
+            State::Degraded
+        } else {
+            State::Ok
+        };
+
+        Status {
+            state,
+            game_version,
+            last_release_refresh,
+            downtime,
+        }
+    }
+}
+
+fn prefers_html(req: &HttpRequest) -> bool {
+    Accept::parse(req).is_ok_and(|accept| {
+        accept
+            .ranked()
+            .first()
+            .is_some_and(|mime| mime.essence_str() == "text/html")
+    })
+}
+
+fn render_html(game_name: &str, status: &Status) -> Result<String, fmt::Error> {
+    let (state, color) = match status.state {
+        State::Ok => ("All systems operational", "#2a2"),
+        State::Degraded => ("Degraded service", "#d80"),
+        State::Maintenance => ("Maintenance in progress", "#36c"),
+    };
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html lang=\"en\">")?;
+    writeln!(html, "<head>")?;
+    writeln!(html, "<meta charset=\"utf-8\">")?;
+    writeln!(
+        html,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    )?;
+    writeln!(html, "<title>{} status</title>", escape(game_name))?;
+    writeln!(
+        html,
+        "<style>body {{ font-family: sans-serif; margin: 2em auto; max-width: 40em; padding: 0 1em; }} \
+         .state {{ color: {color}; }} dt {{ font-weight: bold; }}</style>"
+    )?;
+    writeln!(html, "</head>")?;
+    writeln!(html, "<body>")?;
+    writeln!(html, "<h1 class=\"state\">{state}</h1>")?;
+    writeln!(html, "<dl>")?;
+
+    let game_version = status.game_version.as_deref().unwrap_or("unavailable");
+    writeln!(
+        html,
+        "<dt>Game version</dt><dd>{}</dd>",
+        escape(game_version)
+    )?;
+
+    let last_refresh = status
+        .last_release_refresh
+        .map(|date| date.format(DATE_FORMAT).to_string())
+        .unwrap_or_else(|| "never".to_string());
+    writeln!(html, "<dt>Last release refresh</dt><dd>{last_refresh}</dd>")?;
+
+    if let Some(downtime) = &status.downtime {
+        write!(
+            html,
+            "<dt>Scheduled downtime</dt><dd>{} to {}",
+            downtime.starts_at.format(DATE_FORMAT),
+            downtime.ends_at.format(DATE_FORMAT)
+        )?;
+        if let Some(message) = &downtime.message {
+            write!(html, "<br>{}", escape(message))?;
+        }
+        writeln!(html, "</dd>")?;
+    }
+
+    writeln!(html, "</dl>")?;
+    writeln!(html, "</body>")?;
+    writeln!(html, "</html>")?;
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use crate::configure_app;
+    use crate::testing::{self, GAME_VERSION};
+
+    #[actix_web::test]
+    async fn status_as_json_or_html() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let req = TestRequest::get().uri("/status").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["state"], "ok");
+        assert_eq!(body["game_version"], GAME_VERSION);
+        assert!(body.get("downtime").is_none());
+
+        let req = TestRequest::get()
+            .uri("/status")
+            .insert_header(("Accept", "text/html,application/xhtml+xml;q=0.9"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers().get("vary").unwrap(), "Accept");
+        let html = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(html.contains("All systems operational"));
+        assert!(html.contains(GAME_VERSION));
+    }
+}
@@ -0,0 +1,261 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::game_data::Platform;
+use crate::reporting::report_error;
+use crate::trace_context::{self, spawn_traced};
+use crate::AppData;
+
+/// Maximum size of a telemetry request body, in bytes.
+const PAYLOAD_LIMIT: usize = 16 * 1024;
+const CRASH_ID_MAX_LEN: usize = 64;
+
+/// Batches being forwarded to the sink, shared by every worker.
+#[derive(Default)]
+pub struct TelemetryForwards {
+    pending: AtomicUsize,
+    /// Batches left out by `sample_rate`.
+    sampled_out: AtomicU64,
+    /// Batches dropped because `max_pending_forwards` were already being forwarded.
+    dropped: AtomicU64,
+}
+
+/// Counts of [`TelemetryForwards`], as listed by the admin API. The batches the sink
+/// rejected are counted with the other reported errors.
+#[derive(Serialize)]
+pub struct TelemetryReport {
+    pending: usize,
+    sampled_out: u64,
+    dropped: u64,
+}
+
+/// Releases its place once the batch is forwarded, or forwarding it failed.
+struct PendingForward(web::Data<AppData>);
+
+/// Anonymous client events, which must never carry anything identifying a player.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TelemetryEvent {
+    LauncherStarted {
+        launcher_version: Version,
+        platform: Platform,
+    },
+    UpdateApplied {
+        from_version: Version,
+        to_version: Version,
+        platform: Platform,
+    },
+    Crash {
+        crash_id: String,
+        game_version: Version,
+        platform: Platform,
+    },
+}
+
+#[derive(Deserialize)]
+struct TelemetryBatch {
+    events: Vec<TelemetryEvent>,
+}
+
+#[derive(Serialize)]
+struct ForwardedBatch {
+    received_at: DateTime<Utc>,
+    events: Vec<TelemetryEvent>,
+}
+
+/// Registers the ingestion route, which answers 404 unless a sink is configured to
+/// forward the events to.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/telemetry")
+            .app_data(web::JsonConfig::default().limit(PAYLOAD_LIMIT))
+            .route(web::post().to(ingest)),
+    );
+}
+
+async fn ingest(app_data: web::Data<AppData>, batch: web::Json<TelemetryBatch>) -> impl Responder {
+    let config = &app_data.config;
+    let Some(sink_url) = config.telemetry.sink_url.clone() else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let TelemetryBatch { events } = batch.into_inner();
+
+    if events.is_empty() || events.len() > config.telemetry.max_batch_size {
+        return HttpResponse::BadRequest().body(format!(
+            "a batch must contain between 1 and {} events",
+            config.telemetry.max_batch_size
+        ));
+    }
+
+    for event in &events {
+        if let TelemetryEvent::Crash { crash_id, .. } = event {
+            if crash_id.is_empty()
+                || crash_id.len() > CRASH_ID_MAX_LEN
+                || !crash_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            {
+                return HttpResponse::BadRequest().body("invalid crash id");
+            }
+        }
+    }
+
+    // sampled out batches are acknowledged all the same so clients don't retry them
+    let sample_rate = config.telemetry.sample_rate;
+    if sample_rate < 1.0 && rand::random::<f64>() >= sample_rate {
+        app_data
+            .telemetry_forwards
+            .sampled_out
+            .fetch_add(1, Ordering::Relaxed);
+        log::debug!("telemetry batch sampled out");
+        return HttpResponse::Accepted().finish();
+    }
+
+    let batch = ForwardedBatch {
+        received_at: Utc::now(),
+        events,
+    };
+
+    // a slow sink must not pile up forwards, the batches are dropped until it catches up
+    let Some(pending) = PendingForward::acquire(&app_data) else {
+        let dropped = app_data
+            .telemetry_forwards
+            .dropped
+            .fetch_add(1, Ordering::Relaxed)
+            + 1;
+        log::warn!("dropping a telemetry batch, the sink is too slow ({dropped} dropped so far)");
+        return HttpResponse::Accepted().finish();
+    };
+
+    let client = app_data.http_client.clone();
+    spawn_traced(async move {
+        let result = trace_context::propagate(client.post(sink_url))
+            .json(&batch)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            // the sink url may contain credentials
+            .map_err(reqwest::Error::without_url);
+
+        if let Err(err) = result {
+            report_error("failed to forward telemetry events", &err);
+        }
+        drop(pending);
+    });
+
+    HttpResponse::Accepted().finish()
+}
+
+impl TelemetryForwards {
+    pub fn report(&self) -> TelemetryReport {
+        TelemetryReport {
+            pending: self.pending.load(Ordering::Acquire),
+            sampled_out: self.sampled_out.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl PendingForward {
+    fn acquire(app_data: &web::Data<AppData>) -> Option<Self> {
+        let limit = app_data.config.telemetry.max_pending_forwards;
+        app_data
+            .telemetry_forwards
+            .pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                (pending < limit).then_some(pending + 1)
+            })
+            .ok()
+            .map(|_| Self(app_data.clone()))
+    }
+}
+
+impl Drop for PendingForward {
+    fn drop(&mut self) {
+        self.0
+            .telemetry_forwards
+            .pending
+            .fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use super::*;
+    use crate::configure_app;
+    use crate::testing;
+
+    fn batch() -> TestRequest {
+        TestRequest::post()
+            .uri("/v1/telemetry")
+            .set_json(serde_json::json!({
+                "events": [{
+                    "type": "launcher_started",
+                    "launcher_version": "1.0.0",
+                    "platform": "windows_x64",
+                }],
+            }))
+    }
+
+    #[actix_web::test]
+    async fn sampled_out_batches_are_counted() {
+        let mut config = testing::config();
+        // never reached
+        config.telemetry.sink_url = Some("http://127.0.0.1:9".to_string());
+        config.telemetry.sample_rate = 0.0;
+        let app_data = testing::app_data(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .configure(configure_app),
+        )
+        .await;
+
+        for _ in 0..3 {
+            let res = test::call_service(&app, batch().to_request()).await;
+            assert_eq!(res.status(), 202);
+        }
+
+        let report = app_data.telemetry_forwards.report();
+        assert_eq!(
+            (report.pending, report.sampled_out, report.dropped),
+            (0, 3, 0)
+        );
+    }
+
+    /// A sink which never answers doesn't pile up forwards.
+    #[actix_web::test]
+    async fn batches_are_dropped_while_the_sink_is_slow() {
+        // accepts the connections but never reads them
+        let sink = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = testing::config();
+        config.telemetry.sink_url = Some(format!("http://{}", sink.local_addr().unwrap()));
+        config.telemetry.max_pending_forwards = 2;
+        let app_data = testing::app_data(config).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .configure(configure_app),
+        )
+        .await;
+
+        for _ in 0..5 {
+            let res = test::call_service(&app, batch().to_request()).await;
+            assert_eq!(res.status(), 202);
+        }
+
+        let forwards = &app_data.telemetry_forwards;
+        assert_eq!(forwards.pending.load(Ordering::Acquire), 2);
+        assert_eq!(forwards.dropped.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,293 @@
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::from_fn;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+
+use super::version::{self, GameVersionQuery, VersionError, VersionQuery};
+use super::{admin, events, feeds, status, telemetry};
+use crate::game_data::Platform;
+use crate::{envelope, AppData};
+
+/// Same routes as the unversioned ones, with every body wrapped in the envelope
+/// defined in [`envelope`]. The version routes build their envelopes themselves to
+/// give their errors specific codes, the other route groups are wrapped as they are.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/v2")
+            .service(game_version)
+            .service(updater_version)
+            .service(
+                web::scope("")
+                    .wrap(from_fn(envelope::wrap_responses))
+                    .configure(feeds::configure)
+                    .configure(status::configure)
+                    .configure(events::configure)
+                    .configure(telemetry::configure)
+                    .configure(admin::configure),
+            ),
+    );
+}
+
+#[get("/game_version")]
+async fn game_version(
+    req: HttpRequest,
+    app_data: web::Data<AppData>,
+    ver_query: Result<web::Query<GameVersionQuery>, actix_web::Error>,
+) -> impl Responder {
+    let ver_query = match ver_query {
+        Ok(ver_query) => ver_query,
+        Err(err) => return invalid_query(&req, &err, &app_data.game_platforms().await),
+    };
+
+    match app_data
+        .resolve_game_version(ver_query.platform, ver_query.flavor.as_deref())
+        .await
+    {
+        Ok(game_version) => envelope::data(&req, game_version),
+        Err(err) => version_error(&req, &app_data, err, ver_query.platform),
+    }
+}
+
+#[get("/updater_version")]
+async fn updater_version(
+    req: HttpRequest,
+    app_data: web::Data<AppData>,
+    ver_query: Result<web::Query<VersionQuery>, actix_web::Error>,
+) -> impl Responder {
+    let ver_query = match ver_query {
+        Ok(ver_query) => ver_query,
+        Err(err) => return invalid_query(&req, &err, &app_data.updater_platforms().await),
+    };
+
+    match app_data.resolve_updater_version(ver_query.platform).await {
+        Ok(updater_version) => envelope::data(&req, updater_version),
+        Err(err) => version_error(&req, &app_data, err, ver_query.platform),
+    }
+}
+
+fn version_error(
+    req: &HttpRequest,
+    app_data: &AppData,
+    err: VersionError,
+    platform: Platform,
+) -> HttpResponse {
+    match err {
+        VersionError::NoRelease => {
+            let mut response = envelope::error(
+                req,
+                StatusCode::NOT_FOUND,
+                "no_release_available",
+                "no release has been published yet, retry later",
+                &[],
+            );
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(version::retry_delay(app_data)),
+            );
+            response
+        }
+        VersionError::Unavailable => envelope::error(
+            req,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "release_unavailable",
+            "the latest release couldn't be fetched",
+            &[],
+        ),
+        VersionError::PlatformNotFound(available) => envelope::error(
+            req,
+            StatusCode::BAD_REQUEST,
+            "platform_not_found",
+            "no release available for platform {platform}, available platforms are: {available_platforms}",
+            &[
+                ("platform", platform.as_str()),
+                ("available_platforms", &join_platforms(&available)),
+            ],
+        ),
+        VersionError::UnknownFlavor => envelope::error(
+            req,
+            StatusCode::BAD_REQUEST,
+            "unknown_flavor",
+            "unknown flavor, valid flavors are: {valid_flavors}",
+            &[("valid_flavors", &app_data.config.game.flavors.join(", "))],
+        ),
+    }
+}
+
+/// Rejects a query which couldn't be parsed, such as an unknown platform.
+fn invalid_query(
+    req: &HttpRequest,
+    err: &actix_web::Error,
+    valid_platforms: &[Platform],
+) -> HttpResponse {
+    envelope::error(
+        req,
+        StatusCode::BAD_REQUEST,
+        "invalid_query",
+        "{error}, valid platforms are: {valid_platforms}",
+        &[
+            ("error", &err.to_string()),
+            ("valid_platforms", &join_platforms(valid_platforms)),
+        ],
+    )
+}
+
+fn join_platforms(platforms: &[Platform]) -> String {
+    platforms
+        .iter()
+        .map(Platform::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use crate::configure_app;
+    use crate::logging::log_requests;
+    use crate::testing::{self, ADMIN_TOKEN, GAME_VERSION, UPDATER_VERSION};
+
+    #[actix_web::test]
+    async fn bodies_are_wrapped_in_the_envelope() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let req = TestRequest::get()
+            .uri("/v2/game_version?platform=linux_x86_64")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"]["version"], GAME_VERSION);
+        assert_eq!(body["data"]["updater"]["size"], 1024);
+
+        let req = TestRequest::get()
+            .uri("/v2/updater_version?platform=windows_x64")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"]["version"], UPDATER_VERSION);
+
+        let req = TestRequest::get()
+            .uri("/v2/game_version?platform=linux_x86_64&flavor=unknown")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["error"]["code"], "unknown_flavor");
+        assert!(body.get("data").is_none());
+    }
+
+    /// Both API versions reject a platform the release wasn't built for like an invalid
+    /// query.
+    #[actix_web::test]
+    async fn platform_not_found_is_a_bad_request() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let req = TestRequest::get()
+            .uri("/game_version?platform=macos_arm64")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(
+            body["valid_platforms"],
+            serde_json::json!(["windows_x64", "linux_x86_64"])
+        );
+
+        let req = TestRequest::get()
+            .uri("/v2/game_version?platform=macos_arm64")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["error"]["code"], "platform_not_found");
+
+        let req = TestRequest::get()
+            .uri("/v2/game_version?platform=amiga")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["error"]["code"], "invalid_query");
+        assert!(body["error"]["description"]
+            .as_str()
+            .unwrap()
+            .ends_with("valid platforms are: windows_x64, linux_x86_64"));
+    }
+
+    /// The route groups shared with the unversioned API get the envelope as well, their
+    /// errors included.
+    #[actix_web::test]
+    async fn other_route_groups_are_wrapped_in_the_envelope() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(log_requests))
+                .app_data(app_data)
+                .configure(configure_app),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/v2/status").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"]["game_version"], GAME_VERSION);
+
+        let req = TestRequest::get().uri("/v2/events/active").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"]["events"], serde_json::json!([]));
+
+        let req = TestRequest::get()
+            .uri("/v2/admin/telemetry")
+            .insert_header(("Authorization", format!("Bearer {ADMIN_TOKEN}")))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["data"]["dropped"], 0);
+
+        // the feed isn't JSON, it is served as it is
+        let req = TestRequest::get()
+            .uri("/v2/feeds/releases.xml")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "application/atom+xml; charset=utf-8"
+        );
+
+        for (req, status, code) in [
+            (
+                TestRequest::get().uri("/v2/admin/jobs"),
+                401,
+                "unauthorized",
+            ),
+            (
+                TestRequest::put()
+                    .uri("/v2/admin/releases/pin")
+                    .insert_header(("Authorization", format!("Bearer {ADMIN_TOKEN}")))
+                    .set_json(serde_json::json!({ "version": "not a version" })),
+                400,
+                "bad_request",
+            ),
+            // no sink is configured
+            (
+                TestRequest::post()
+                    .uri("/v2/telemetry")
+                    .set_json(serde_json::json!({ "events": [] })),
+                404,
+                "not_found",
+            ),
+            (TestRequest::get().uri("/v2/unknown"), 404, "not_found"),
+        ] {
+            let res = test::call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), status);
+            let request_id = res.headers().get("x-request-id").cloned();
+            let body: serde_json::Value = test::read_body_json(res).await;
+            assert_eq!(body["error"]["code"], code);
+            assert!(!body["error"]["description"].as_str().unwrap().is_empty());
+            assert_eq!(
+                body["error"]["request_id"],
+                request_id.unwrap().to_str().unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use actix_web::http::header;
+use actix_web::web::Bytes;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_names::DEFAULT_FLAVOR;
+use crate::fetcher::FetcherError;
+use crate::game_data::{Assets, GameRelease, GameVersion, Platform, UpdaterVersion};
+use crate::negotiation::{self, Format};
+use crate::reporting::report_error;
+use crate::AppData;
+
+#[derive(Deserialize)]
+pub(super) struct VersionQuery {
+    pub(super) platform: Platform,
+}
+
+#[derive(Deserialize)]
+pub(super) struct GameVersionQuery {
+    pub(super) platform: Platform,
+    pub(super) flavor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct InvalidQuery {
+    error: String,
+    valid_platforms: Vec<&'static str>,
+}
+
+/// Why a version couldn't be served, the cause has already been logged or reported.
+pub(super) enum VersionError {
+    /// Nothing was published yet, which is expected from a new deployment.
+    NoRelease,
+    Unavailable,
+    /// With the platforms the current release has.
+    PlatformNotFound(Vec<Platform>),
+    UnknownFlavor,
+}
+
+impl AppData {
+    pub(super) async fn resolve_game_version(
+        &self,
+        platform: Platform,
+        flavor: Option<&str>,
+    ) -> Result<GameVersion, VersionError> {
+        let flavor = flavor.unwrap_or(DEFAULT_FLAVOR);
+        if !self.config.game.flavors.iter().any(|f| f == flavor) {
+            return Err(VersionError::UnknownFlavor);
+        }
+
+        let updater_release = match self.latest_updater_release().await {
+            Ok(updater_release) => updater_release,
+            Err(err) if matches!(*err, FetcherError::NoReleaseFound) => {
+                log::warn!("no updater release published yet");
+                return Err(VersionError::NoRelease);
+            }
+            Err(err) => {
+                report_error("failed to fetch the latest updater release", &*err);
+                return Err(VersionError::Unavailable);
+            }
+        };
+
+        let game_release = match self.latest_game_release(flavor).await {
+            Ok(game_release) => game_release,
+            Err(err) if matches!(*err, FetcherError::NoReleaseFound) => {
+                log::warn!("no game release published yet");
+                return Err(VersionError::NoRelease);
+            }
+            Err(err) => {
+                report_error("failed to fetch the latest game release", &*err);
+                return Err(VersionError::Unavailable);
+            }
+        };
+
+        let (Some(updater), Some(binary)) = (
+            updater_release.get(&platform),
+            game_release.binaries.get(&platform),
+        ) else {
+            log::warn!(
+                platform = platform.as_str();
+                "no updater or game binary release found for platform {platform}"
+            );
+            return Err(VersionError::PlatformNotFound(served_game_platforms(
+                &updater_release,
+                &game_release,
+            )));
+        };
+
+        Ok(GameVersion {
+            assets: game_release.assets.clone(),
+            assets_version: game_release.assets_version.to_string(),
+            binaries: binary.clone(),
+            packs: self.platform_packs(platform).await,
+            updater: updater.clone(),
+            version: game_release.version.to_string(),
+            downtime: self
+                .downtime()
+                .await
+                .and_then(|downtime| downtime.notice(Utc::now())),
+        })
+    }
+
+    pub(super) async fn resolve_updater_version(
+        &self,
+        platform: Platform,
+    ) -> Result<UpdaterVersion, VersionError> {
+        let updater_release = match self.latest_updater_release().await {
+            Ok(updater_release) => updater_release,
+            Err(err) if matches!(*err, FetcherError::NoReleaseFound) => {
+                log::warn!("no updater release published yet");
+                return Err(VersionError::NoRelease);
+            }
+            Err(err) => {
+                report_error("failed to fetch the latest updater release", &*err);
+                return Err(VersionError::Unavailable);
+            }
+        };
+
+        let Some(updater) = updater_release.get(&platform) else {
+            log::warn!(
+                platform = platform.as_str();
+                "no updater release found for platform {platform}"
+            );
+            return Err(VersionError::PlatformNotFound(served_updater_platforms(
+                &updater_release,
+            )));
+        };
+
+        Ok(UpdaterVersion::from(updater))
+    }
+
+    /// Platforms /game_version can serve, every known one while the releases can't be
+    /// fetched.
+    pub(super) async fn game_platforms(&self) -> Vec<Platform> {
+        let (Ok(updater_release), Ok(game_release)) = (
+            self.latest_updater_release().await,
+            self.latest_game_release(DEFAULT_FLAVOR).await,
+        ) else {
+            return Platform::ALL.to_vec();
+        };
+        served_game_platforms(&updater_release, &game_release)
+    }
+
+    /// Platforms /updater_version can serve, every known one while the release can't be
+    /// fetched.
+    pub(super) async fn updater_platforms(&self) -> Vec<Platform> {
+        match self.latest_updater_release().await {
+            Ok(updater_release) => served_updater_platforms(&updater_release),
+            Err(_) => Platform::ALL.to_vec(),
+        }
+    }
+
+    /// Returns the serialized game version, which is the same for every client of a
+    /// platform, so it is only resolved and serialized again when it may have changed.
+    async fn game_version_body(
+        &self,
+        platform: Platform,
+        flavor: Option<&str>,
+        format: Format,
+    ) -> Result<Bytes, VersionError> {
+        let key = (
+            platform,
+            flavor.unwrap_or(DEFAULT_FLAVOR).to_string(),
+            format,
+        );
+        if let Some(body) = self.game_version_responses.get(&key).await {
+            return Ok(body);
+        }
+
+        let resolved = self.resolve_game_version(platform, flavor).await?;
+        let body = negotiation::encode(format, &resolved).ok_or(VersionError::Unavailable)?;
+        self.game_version_responses.insert(key, body.clone()).await;
+        Ok(body)
+    }
+}
+
+#[get("/game_version")]
+pub(super) async fn game_version(
+    req: HttpRequest,
+    app_data: web::Data<AppData>,
+    ver_query: Result<web::Query<GameVersionQuery>, actix_web::Error>,
+) -> impl Responder {
+    let ver_query = match ver_query {
+        Ok(ver_query) => ver_query,
+        Err(err) => return invalid_query(&err, &app_data.game_platforms().await),
+    };
+
+    let format = Format::from_request(&req);
+    match app_data
+        .game_version_body(ver_query.platform, ver_query.flavor.as_deref(), format)
+        .await
+    {
+        Ok(body) => negotiation::respond_encoded(HttpResponse::Ok(), format, body),
+        Err(VersionError::NoRelease) => no_release(&app_data),
+        Err(VersionError::Unavailable) => HttpResponse::InternalServerError().finish(),
+        Err(VersionError::PlatformNotFound(available)) => {
+            platform_not_found(ver_query.platform, &available)
+        }
+        Err(VersionError::UnknownFlavor) => HttpResponse::BadRequest().body(format!(
+            "unknown flavor, valid flavors are: {}",
+            app_data.config.game.flavors.join(", ")
+        )),
+    }
+}
+
+#[get("/updater_version")]
+pub(super) async fn updater_version(
+    req: HttpRequest,
+    app_data: web::Data<AppData>,
+    ver_query: Result<web::Query<VersionQuery>, actix_web::Error>,
+) -> impl Responder {
+    let ver_query = match ver_query {
+        Ok(ver_query) => ver_query,
+        Err(err) => return invalid_query(&err, &app_data.updater_platforms().await),
+    };
+
+    match app_data.resolve_updater_version(ver_query.platform).await {
+        Ok(updater_version) => negotiation::respond(&req, HttpResponse::Ok(), &updater_version),
+        Err(VersionError::NoRelease) => no_release(&app_data),
+        Err(VersionError::Unavailable) => HttpResponse::InternalServerError().finish(),
+        Err(VersionError::PlatformNotFound(available)) => {
+            platform_not_found(ver_query.platform, &available)
+        }
+        Err(VersionError::UnknownFlavor) => unreachable!("the updater has no flavors"),
+    }
+}
+
+/// Seconds clients are asked to wait before looking for a first release again, releases
+/// aren't expected to show up more often than the cache expires.
+pub(super) fn retry_delay(app_data: &AppData) -> u64 {
+    app_data.config.cache.lifespan
+}
+
+/// Rejected like an unknown platform, listing the platforms which can actually be served.
+fn platform_not_found(platform: Platform, available: &[Platform]) -> HttpResponse {
+    HttpResponse::BadRequest().json(InvalidQuery {
+        error: format!("no release available for platform {platform}"),
+        valid_platforms: available.iter().map(Platform::as_str).collect(),
+    })
+}
+
+fn no_release(app_data: &AppData) -> HttpResponse {
+    HttpResponse::NotFound()
+        .insert_header((header::RETRY_AFTER, retry_delay(app_data)))
+        .finish()
+}
+
+/// Rejects a query which couldn't be parsed, such as an unknown platform.
+fn invalid_query(err: &actix_web::Error, valid_platforms: &[Platform]) -> HttpResponse {
+    HttpResponse::BadRequest().json(InvalidQuery {
+        error: err.to_string(),
+        valid_platforms: valid_platforms.iter().map(Platform::as_str).collect(),
+    })
+}
+
+/// Platforms with both an updater and a game binary.
+fn served_game_platforms(updater_release: &Assets, game_release: &GameRelease) -> Vec<Platform> {
+    Platform::ALL
+        .into_iter()
+        .filter(|platform| {
+            updater_release.contains_key(platform) && game_release.binaries.contains_key(platform)
+        })
+        .collect()
+}
+
+fn served_updater_platforms(updater_release: &Assets) -> Vec<Platform> {
+    Platform::ALL
+        .into_iter()
+        .filter(|platform| updater_release.contains_key(platform))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+    use futures::future::join_all;
+
+    use crate::configure_app;
+    use crate::testing::{self, GAME_PLATFORMS, GAME_VERSION, UPDATER_VERSION};
+
+    /// Many clients asking for their version at once are all served from the cached
+    /// releases, without reaching GitHub. The contention is measured by the
+    /// `cache_contention` benchmark.
+    #[actix_web::test]
+    async fn concurrent_game_version_requests() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let responses = join_all((0..512).map(|i| {
+            let platform = GAME_PLATFORMS[i % GAME_PLATFORMS.len()];
+            let req = TestRequest::get()
+                .uri(&format!("/game_version?platform={platform}"))
+                .to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req)
+        }))
+        .await;
+
+        for (i, body) in responses.iter().enumerate() {
+            let platform = GAME_PLATFORMS[i % GAME_PLATFORMS.len()];
+            assert_eq!(body["version"], GAME_VERSION);
+            assert_eq!(
+                body["binaries"]["download_url"],
+                format!("https://github.invalid/download/{GAME_VERSION}/{platform}.zip")
+            );
+            assert_eq!(body, &responses[i % GAME_PLATFORMS.len()]);
+        }
+    }
+
+    #[actix_web::test]
+    async fn updater_version_by_platform() {
+        let app_data = testing::app_data(testing::config()).await;
+        let app = test::init_service(App::new().app_data(app_data).configure(configure_app)).await;
+
+        let req = TestRequest::get()
+            .uri("/updater_version?platform=macos_arm64")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["version"], UPDATER_VERSION);
+        assert_eq!(
+            body["download_url"],
+            format!(
+                "https://github.invalid/download/{UPDATER_VERSION}/macos_arm64_this_updater_of_mine.zip"
+            )
+        );
+
+        let req = TestRequest::get()
+            .uri("/updater_version?platform=amiga")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(
+            body["valid_platforms"],
+            serde_json::json!(["windows_x64", "linux_x86_64", "macos_arm64"])
+        );
+
+        // the game has no macOS build
+        let req = TestRequest::get()
+            .uri("/game_version?platform=amiga")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(
+            body["valid_platforms"],
+            serde_json::json!(["windows_x64", "linux_x86_64"])
+        );
+    }
+
+    /// Once the cached updater release expires, an updater repository outage keeps serving
+    /// the last release fetched.
+    #[actix_web::test]
+    async fn updater_release_is_served_stale_on_error() {
+        let mut config = testing::config();
+        // fails before reaching GitHub, it can't even be put in a url
+        config.github.repo_owner = "unreachable owner".to_string();
+        let app_data = testing::app_data(config).await;
+        app_data.cache.invalidate("latest_updater_release").await;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .configure(configure_app),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/game_version?platform=windows_x64")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 500);
+
+        app_data.remember_updater_release(testing::updater_release());
+        let req = TestRequest::get()
+            .uri("/game_version?platform=windows_x64")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["version"], GAME_VERSION);
+        assert!(body["updater"]["download_url"]
+            .as_str()
+            .unwrap()
+            .contains(UPDATER_VERSION));
+    }
+}
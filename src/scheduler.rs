@@ -0,0 +1,239 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use actix_web::rt::{spawn, time};
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use futures::future::{FutureExt, LocalBoxFuture};
+use serde::{Deserialize, Serialize};
+
+use crate::reporting::report_error;
+use crate::AppData;
+
+pub type JobResult = Result<(), Box<dyn Error>>;
+pub type JobFn = fn(web::Data<AppData>) -> LocalBoxFuture<'static, JobResult>;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobConfig {
+    /// Seconds between two runs, the job is disabled when set to 0.
+    pub interval: u64,
+    /// Upper bound of the random delay, in seconds, added to every interval.
+    pub jitter: u64,
+}
+
+/// Runs the registered jobs periodically in the background, never running a job
+/// while its previous run is still in progress.
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<Arc<Job>>,
+}
+
+struct Job {
+    name: &'static str,
+    config: JobConfig,
+    run: JobFn,
+    running: AtomicBool,
+    stats: JobStats,
+}
+
+#[derive(Default)]
+struct JobStats {
+    runs: AtomicU64,
+    failures: AtomicU64,
+    skipped: AtomicU64,
+    last_duration_ms: AtomicU64,
+    last_run: RwLock<Option<DateTime<Utc>>>,
+    last_success: RwLock<Option<DateTime<Utc>>>,
+}
+
+#[derive(Serialize)]
+pub struct JobReport {
+    name: &'static str,
+    interval: u64,
+    jitter: u64,
+    running: bool,
+    runs: u64,
+    failures: u64,
+    skipped: u64,
+    last_duration_ms: u64,
+    last_run: Option<DateTime<Utc>>,
+    last_success: Option<DateTime<Utc>>,
+}
+
+impl Scheduler {
+    pub fn register(&mut self, name: &'static str, config: JobConfig, run: JobFn) {
+        self.jobs.push(Arc::new(Job {
+            name,
+            config,
+            run,
+            running: AtomicBool::new(false),
+            stats: JobStats::default(),
+        }));
+    }
+
+    pub fn start(&self, app_data: web::Data<AppData>) {
+        for job in self.jobs.iter().filter(|job| job.config.interval > 0) {
+            let job = job.clone();
+            let app_data = app_data.clone();
+
+            spawn(async move {
+                loop {
+                    time::sleep(job.next_delay()).await;
+
+                    if job.running.swap(true, Ordering::AcqRel) {
+                        log::warn!("job {} is still running, skipping this run", job.name);
+                        job.stats.skipped.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    spawn(job.clone().run_once(app_data.clone()));
+                }
+            });
+        }
+    }
+
+    pub fn report(&self) -> Vec<JobReport> {
+        self.jobs.iter().map(|job| job.report()).collect()
+    }
+
+    /// When the job last succeeded, None when it never did or doesn't exist.
+    pub fn last_success(&self, name: &str) -> Option<DateTime<Utc>> {
+        let job = self.jobs.iter().find(|job| job.name == name)?;
+        let last_success = *job.stats.last_success.read().unwrap();
+        last_success
+    }
+}
+
+/// Clears the running flag of the job when its run ends, even when it is cancelled, so
+/// the job isn't skipped forever.
+struct RunningGuard<'a>(&'a Job);
+
+/// Panic of a job, reported like the errors it returns.
+#[derive(Debug)]
+struct JobPanic(String);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::Release);
+    }
+}
+
+impl JobPanic {
+    fn new(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "unknown payload".to_string(),
+            },
+        };
+        Self(message)
+    }
+}
+
+impl fmt::Display for JobPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked: {}", self.0)
+    }
+}
+
+impl Error for JobPanic {}
+
+impl Job {
+    fn next_delay(&self) -> Duration {
+        let jitter = match self.config.jitter {
+            0 => 0,
+            jitter => rand::random_range(0..=jitter * 1000),
+        };
+
+        Duration::from_secs(self.config.interval) + Duration::from_millis(jitter)
+    }
+
+    async fn run_once(self: Arc<Self>, app_data: web::Data<AppData>) {
+        let _running = RunningGuard(&self);
+        let start = Instant::now();
+        let result = match AssertUnwindSafe((self.run)(app_data)).catch_unwind().await {
+            Ok(result) => result,
+            Err(payload) => Err(JobPanic::new(payload).into()),
+        };
+
+        let stats = &self.stats;
+        stats.runs.fetch_add(1, Ordering::Relaxed);
+        stats
+            .last_duration_ms
+            .store(start.elapsed().as_millis() as u64, Ordering::Relaxed);
+        let now = Utc::now();
+        *stats.last_run.write().unwrap() = Some(now);
+
+        match result {
+            Ok(()) => *stats.last_success.write().unwrap() = Some(now),
+            Err(err) => {
+                report_error(self.name, &*err);
+                stats.failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn report(&self) -> JobReport {
+        let stats = &self.stats;
+        JobReport {
+            name: self.name,
+            interval: self.config.interval,
+            jitter: self.config.jitter,
+            running: self.running.load(Ordering::Acquire),
+            runs: stats.runs.load(Ordering::Relaxed),
+            failures: stats.failures.load(Ordering::Relaxed),
+            skipped: stats.skipped.load(Ordering::Relaxed),
+            last_duration_ms: stats.last_duration_ms.load(Ordering::Relaxed),
+            last_run: *stats.last_run.read().unwrap(),
+            last_success: *stats.last_success.read().unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reporting, testing};
+
+    #[actix_web::test]
+    async fn panicking_job_runs_again() {
+        let mut scheduler = Scheduler::default();
+        let config = JobConfig {
+            interval: 60,
+            jitter: 0,
+        };
+        scheduler.register("panicking_job", config, |_| {
+            Box::pin(async { panic!("job panicked") })
+        });
+        let job = scheduler.jobs[0].clone();
+
+        // taken by the loop of the job before each run
+        job.running.store(true, Ordering::Release);
+        let app_data = testing::app_data(testing::config()).await;
+        job.clone().run_once(app_data).await;
+
+        let report = job.report();
+        assert!(!report.running);
+        assert_eq!((report.runs, report.failures), (1, 1));
+        assert_eq!(reporting::error_counts().get("panicking_job"), Some(&1));
+    }
+
+    #[test]
+    fn panic_messages() {
+        let message = JobPanic::new(Box::new("static message"));
+        assert_eq!(message.to_string(), "panicked: static message");
+        let message = JobPanic::new(Box::new(format!("formatted {}", 1)));
+        assert_eq!(message.to_string(), "panicked: formatted 1");
+        assert_eq!(
+            JobPanic::new(Box::new(1)).to_string(),
+            "panicked: unknown payload"
+        );
+    }
+}
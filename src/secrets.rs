@@ -0,0 +1,80 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use secure_string::SecureString;
+use serde::{Deserialize, Serialize};
+
+/// How the `*_file` keys of the configuration are read.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretProviderKind {
+    /// The keys are paths, such as Docker or Kubernetes secret mounts.
+    #[default]
+    File,
+    /// The keys are names of the credentials passed by systemd with `LoadCredential=`.
+    Systemd,
+}
+
+/// Source of the secrets which shouldn't be written in the configuration file.
+pub trait SecretProvider {
+    fn read(&self, reference: &str) -> io::Result<SecureString>;
+}
+
+pub struct FileProvider;
+
+impl SecretProvider for FileProvider {
+    fn read(&self, path: &str) -> io::Result<SecureString> {
+        read_secret_file(Path::new(path))
+    }
+}
+
+pub struct SystemdCredentials {
+    directory: PathBuf,
+}
+
+impl SystemdCredentials {
+    /// systemd only sets `CREDENTIALS_DIRECTORY` for the services having credentials.
+    pub fn from_env() -> io::Result<Self> {
+        match env::var_os("CREDENTIALS_DIRECTORY") {
+            Some(directory) => Ok(Self {
+                directory: directory.into(),
+            }),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "CREDENTIALS_DIRECTORY is not set, the service has no credentials",
+            )),
+        }
+    }
+}
+
+impl SecretProvider for SystemdCredentials {
+    fn read(&self, name: &str) -> io::Result<SecureString> {
+        if name.is_empty() || name.contains('/') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("\"{name}\" is not a credential name"),
+            ));
+        }
+
+        read_secret_file(&self.directory.join(name))
+    }
+}
+
+impl SecretProviderKind {
+    pub fn provider(self) -> io::Result<Box<dyn SecretProvider>> {
+        Ok(match self {
+            Self::File => Box::new(FileProvider),
+            Self::Systemd => Box::new(SystemdCredentials::from_env()?),
+        })
+    }
+}
+
+fn read_secret_file(path: &Path) -> io::Result<SecureString> {
+    let mut secret = fs::read_to_string(path)?;
+    // most tools writing secret files end them with a newline
+    let len = secret.trim_end_matches(['\r', '\n']).len();
+    secret.truncate(len);
+    Ok(secret.into())
+}
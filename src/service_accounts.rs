@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const KEY_PREFIX: &str = "tsomsa_";
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+const GRANTED_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// API key given to tooling, such as a stats widget or a Discord bot, which only
+/// reaches some of the admin routes. Only the hash of the key is kept.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ServiceAccount {
+    pub key_sha256: String,
+    /// Admin routes reachable with the key, as a method and a path relative to /admin
+    /// such as "GET /stats/downloads". The bare paths of older accounts only allow GET.
+    pub routes: Vec<String>,
+    /// Requests per minute, 0 for no limit.
+    pub rate_limit: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Service accounts by name.
+pub type ServiceAccounts = BTreeMap<String, ServiceAccount>;
+
+impl ServiceAccount {
+    /// Creates the account along with its key, which can't be retrieved afterwards.
+    pub fn generate(routes: Vec<String>, rate_limit: u32) -> (Self, String) {
+        let key = format!("{KEY_PREFIX}{}", hex::encode(rand::random::<[u8; 32]>()));
+        let account = Self {
+            key_sha256: hash_key(&key),
+            routes,
+            rate_limit,
+            created_at: Utc::now(),
+        };
+        (account, key)
+    }
+
+    pub fn allows(&self, method: &Method, route: &str) -> bool {
+        self.routes.iter().any(|grant| match grant.split_once(' ') {
+            Some((allowed_method, allowed_route)) => {
+                allowed_method == method.as_str() && allowed_route == route
+            }
+            None => grant == route && method == Method::GET,
+        })
+    }
+}
+
+pub fn validate_routes(routes: &[String]) -> Result<(), String> {
+    for grant in routes {
+        let Some((method, route)) = grant.split_once(' ') else {
+            return Err(format!(
+                "route \"{grant}\" must be a method and a path, such as \"GET /jobs\""
+            ));
+        };
+        if !GRANTED_METHODS.contains(&method) {
+            return Err(format!(
+                "route \"{grant}\" has an unknown method, valid methods are: {}",
+                GRANTED_METHODS.join(", ")
+            ));
+        }
+        if !route.starts_with('/') {
+            return Err(format!("route \"{grant}\" must start with '/'"));
+        }
+        // a key allowed to manage the accounts could give itself every route
+        if route.starts_with("/service_accounts") {
+            return Err("service accounts can't manage service accounts".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the name and account owning the key.
+pub fn find<'a>(
+    accounts: &'a ServiceAccounts,
+    key: &str,
+) -> Option<(&'a String, &'a ServiceAccount)> {
+    if !key.starts_with(KEY_PREFIX) {
+        return None;
+    }
+
+    let key_sha256 = hash_key(key);
+    accounts
+        .iter()
+        .find(|(_, account)| account.key_sha256 == key_sha256)
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Counts the requests of each account over fixed one minute windows. The counts are
+/// kept by each instance, so the limit applies per instance.
+#[derive(Default)]
+pub struct RateLimiter {
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Counts a request, returning the seconds until the next window when over the limit.
+    pub fn check(&self, name: &str, limit: u32) -> Result<(), u64> {
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (started_at, count) = windows.entry(name.to_string()).or_insert((now, 0));
+        if now.duration_since(*started_at) >= RATE_LIMIT_WINDOW {
+            *started_at = now;
+            *count = 0;
+        }
+
+        if *count >= limit {
+            let remaining = RATE_LIMIT_WINDOW.saturating_sub(now.duration_since(*started_at));
+            return Err(remaining.as_secs().max(1));
+        }
+
+        *count += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use crate::game_data::{Asset, Assets, GameRelease};
+
+/// Describes the differences between the releases served and the ones fetched from the
+/// shadow source, the download urls are expected to differ and aren't compared.
+pub fn diff_game_releases(primary: &GameRelease, shadow: &GameRelease) -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    if primary.version != shadow.version {
+        mismatches.push(format!(
+            "version: {} != {}",
+            primary.version, shadow.version
+        ));
+    }
+    if primary.assets_version != shadow.assets_version {
+        mismatches.push(format!(
+            "assets version: {} != {}",
+            primary.assets_version, shadow.assets_version
+        ));
+    }
+    diff_asset("assets", &primary.assets, &shadow.assets, &mut mismatches);
+    diff_assets(&primary.binaries, &shadow.binaries, &mut mismatches);
+
+    mismatches
+}
+
+pub fn diff_updater_releases(primary: &Assets, shadow: &Assets) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    diff_assets(primary, shadow, &mut mismatches);
+    mismatches
+}
+
+fn diff_assets(primary: &Assets, shadow: &Assets, mismatches: &mut Vec<String>) {
+    let mut platforms = primary.keys().chain(shadow.keys()).collect::<Vec<_>>();
+    platforms.sort_unstable_by_key(|platform| platform.as_str());
+    platforms.dedup();
+
+    for platform in platforms {
+        match (primary.get(platform), shadow.get(platform)) {
+            (Some(primary), Some(shadow)) => {
+                diff_asset(platform.as_str(), primary, shadow, mismatches)
+            }
+            (Some(_), None) => mismatches.push(format!("{platform}: missing from the shadow")),
+            (None, Some(_)) => mismatches.push(format!("{platform}: only in the shadow")),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_asset(name: &str, primary: &Asset, shadow: &Asset, mismatches: &mut Vec<String>) {
+    if primary.name != shadow.name {
+        mismatches.push(format!("{name}: name {} != {}", primary.name, shadow.name));
+    }
+    if primary.version != shadow.version {
+        mismatches.push(format!(
+            "{name}: version {} != {}",
+            primary.version, shadow.version
+        ));
+    }
+    if primary.size != shadow.size {
+        mismatches.push(format!("{name}: size {} != {}", primary.size, shadow.size));
+    }
+    if primary.sha256 != shadow.sha256 {
+        mismatches.push(format!(
+            "{name}: sha256 {} != {}",
+            primary.sha256.as_deref().unwrap_or("none"),
+            shadow.sha256.as_deref().unwrap_or("none")
+        ));
+    }
+}
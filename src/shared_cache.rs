@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use actix_web::rt::time;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::downtime::Downtime;
+use crate::events::Events;
+use crate::game_data::{Asset, Assets, DownloadStats, GameRelease, Platform};
+use crate::game_release_key;
+use crate::service_accounts::ServiceAccounts;
+
+/// Longest time an instance may hold the lock of a fetch, should it die while fetching.
+const LOCK_LIFESPAN: Duration = Duration::from_secs(30);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Long enough for every instance to have seen the release once announced.
+const ANNOUNCEMENT_LIFESPAN: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+// only deletes the lock if it's still ours, it may have expired and been taken again
+const UNLOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Redis cache shared by every API instance, so a fresh instance doesn't have to
+/// hit GitHub when another one already fetched the releases.
+pub struct SharedCache {
+    connection: ConnectionManager,
+    key_prefix: String,
+    lifespan: u64,
+    updater_lifespan: u64,
+}
+
+// Asset skips some fields to match the public API, these mirrors keep everything.
+#[derive(Serialize, Deserialize)]
+struct StoredAsset {
+    size: i64,
+    name: String,
+    version: Version,
+    // missing from the entries written before it was added
+    #[serde(default)]
+    content_type: String,
+    download_url: String,
+    sha256: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredGameRelease {
+    assets: StoredAsset,
+    assets_version: Version,
+    binaries: HashMap<Platform, StoredAsset>,
+    changelog: Option<String>,
+    version: Version,
+}
+
+impl SharedCache {
+    pub async fn connect(
+        url: &str,
+        key_prefix: &str,
+        lifespan: u64,
+        updater_lifespan: u64,
+    ) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(url)?;
+        Ok(Self {
+            connection: ConnectionManager::new(client).await?,
+            key_prefix: key_prefix.to_string(),
+            lifespan,
+            updater_lifespan,
+        })
+    }
+
+    pub async fn get_game_release(&self, flavor: &str) -> Option<GameRelease> {
+        self.get::<StoredGameRelease>(&game_release_key(flavor))
+            .await
+            .map(Into::into)
+    }
+
+    pub async fn set_game_release(&self, flavor: &str, release: &GameRelease) {
+        self.set(
+            &game_release_key(flavor),
+            &StoredGameRelease::from(release),
+            self.lifespan,
+        )
+        .await
+    }
+
+    pub async fn remove_game_release(&self, flavor: &str) {
+        let key = format!("{}{}", self.key_prefix, game_release_key(flavor));
+        let result: redis::RedisResult<()> = self.connection.clone().del(&key).await;
+        if let Err(err) = result {
+            log::warn!("failed to remove {key} from redis: {err}");
+        }
+    }
+
+    pub async fn get_updater_release(&self) -> Option<Assets> {
+        self.get::<HashMap<Platform, StoredAsset>>("latest_updater_release")
+            .await
+            .map(from_stored_assets)
+    }
+
+    pub async fn set_updater_release(&self, release: &Assets) {
+        self.set(
+            "latest_updater_release",
+            &to_stored_assets(release),
+            self.updater_lifespan,
+        )
+        .await
+    }
+
+    pub async fn get_pinned_game_version(&self) -> Option<Version> {
+        self.get("pinned_game_version").await
+    }
+
+    /// Stores the pin without expiration, unlike the cached releases.
+    pub async fn set_pinned_game_version(&self, version: Option<&Version>) {
+        self.set_persistent("pinned_game_version", version).await;
+    }
+
+    pub async fn get_downtime(&self) -> Option<Downtime> {
+        self.get("downtime").await
+    }
+
+    pub async fn set_downtime(&self, downtime: Option<&Downtime>) {
+        self.set_persistent("downtime", downtime).await;
+    }
+
+    /// Returns None when the events can't be read, so an edit doesn't overwrite them.
+    pub async fn get_events(&self) -> Option<Events> {
+        self.get_persistent("events").await
+    }
+
+    /// Stores the events without expiration, unlike the cached releases.
+    pub async fn set_events(&self, events: &Events) -> bool {
+        self.set_persistent("events", Some(events)).await
+    }
+
+    /// Returns None when the accounts can't be read, so an edit doesn't overwrite them.
+    pub async fn get_service_accounts(&self) -> Option<ServiceAccounts> {
+        self.get_persistent("service_accounts").await
+    }
+
+    pub async fn set_service_accounts(&self, accounts: &ServiceAccounts) -> bool {
+        self.set_persistent("service_accounts", Some(accounts))
+            .await
+    }
+
+    /// Runs `fetch` on a single instance at a time for `key`, so an expired release isn't
+    /// fetched from GitHub by every instance at once. The other instances wait for the lock
+    /// to be released and `read` what the fetch stored, falling back to fetching themselves
+    /// when nothing was stored, such as when the fetch failed.
+    pub async fn single_flight<T, E, R, RFut, F, FFut>(
+        &self,
+        key: &str,
+        read: R,
+        fetch: F,
+    ) -> Result<T, E>
+    where
+        R: Fn() -> RFut,
+        RFut: Future<Output = Option<T>>,
+        F: FnOnce() -> FFut,
+        FFut: Future<Output = Result<T, E>>,
+    {
+        let lock = format!("{}lock:{key}", self.key_prefix);
+        let token = hex::encode(rand::random::<[u8; 16]>());
+
+        loop {
+            match self.try_lock(&lock, &token).await {
+                // redis being unreachable shouldn't prevent fetching
+                Ok(true) | Err(_) => break,
+                Ok(false) => {}
+            }
+
+            while self.is_locked(&lock).await {
+                time::sleep(LOCK_POLL_INTERVAL).await;
+            }
+            if let Some(value) = read().await {
+                return Ok(value);
+            }
+            // the other instance failed, try taking over its fetch
+        }
+
+        let result = fetch().await;
+        self.unlock(&lock, &token).await;
+        result
+    }
+
+    /// Claims the announcement of a game release for this instance, returns whether it
+    /// should announce it.
+    pub async fn claim_announcement(&self, version: &Version) -> bool {
+        self.claim(&format!("announced:{version}"), ANNOUNCEMENT_LIFESPAN)
+            .await
+    }
+
+    /// Claims a task run by every instance for the lifespan, returns whether this instance
+    /// got it, or whether redis can't be reached so the task isn't skipped everywhere.
+    pub async fn claim(&self, name: &str, lifespan: Duration) -> bool {
+        let key = format!("{}claim:{name}", self.key_prefix);
+        self.set_nx(&key, "1", lifespan).await.unwrap_or(true)
+    }
+
+    /// Appends the snapshot to the history, which is kept without expiration and trimmed
+    /// to its `capacity` latest snapshots.
+    pub async fn push_download_stats(&self, stats: &DownloadStats, capacity: usize) {
+        let key = format!("{}download_stats_history", self.key_prefix);
+        let value = serde_json::to_string(stats).expect("stored values are serializable");
+        let result: redis::RedisResult<()> = redis::pipe()
+            .atomic()
+            .rpush(&key, value)
+            .ignore()
+            .ltrim(&key, -(capacity as isize), -1)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await;
+        if let Err(err) = result {
+            log::warn!("failed to write {key} to redis: {err}");
+        }
+    }
+
+    /// Returns the snapshots oldest first, or None when they can't be read.
+    pub async fn get_download_stats_history(&self) -> Option<Vec<DownloadStats>> {
+        let key = format!("{}download_stats_history", self.key_prefix);
+        let values: Vec<String> = match self.connection.clone().lrange(&key, 0, -1).await {
+            Ok(values) => values,
+            Err(err) => {
+                log::warn!("failed to read {key} from redis: {err}");
+                return None;
+            }
+        };
+
+        let snapshots = values
+            .iter()
+            .filter_map(|value| match serde_json::from_str(value) {
+                Ok(snapshot) => Some(snapshot),
+                Err(err) => {
+                    log::warn!("ignoring malformed snapshot in {key}: {err}");
+                    None
+                }
+            })
+            .collect();
+        Some(snapshots)
+    }
+
+    async fn try_lock(&self, lock: &str, token: &str) -> redis::RedisResult<bool> {
+        self.set_nx(lock, token, LOCK_LIFESPAN).await
+    }
+
+    /// Sets the key unless it exists, returns whether it was set.
+    async fn set_nx(&self, key: &str, value: &str, lifespan: Duration) -> redis::RedisResult<bool> {
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::PX(lifespan.as_millis() as u64));
+        let result: redis::RedisResult<Option<String>> = self
+            .connection
+            .clone()
+            .set_options(key, value, options)
+            .await;
+        match result {
+            Ok(set) => Ok(set.is_some()),
+            Err(err) => {
+                log::warn!("failed to take {key} from redis: {err}");
+                Err(err)
+            }
+        }
+    }
+
+    async fn is_locked(&self, lock: &str) -> bool {
+        let result: redis::RedisResult<bool> = self.connection.clone().exists(lock).await;
+        result.unwrap_or_else(|err| {
+            log::warn!("failed to read {lock} from redis: {err}");
+            false
+        })
+    }
+
+    async fn unlock(&self, lock: &str, token: &str) {
+        let result: redis::RedisResult<i64> = redis::Script::new(UNLOCK_SCRIPT)
+            .key(lock)
+            .arg(token)
+            .invoke_async(&mut self.connection.clone())
+            .await;
+        if let Err(err) = result {
+            log::warn!("failed to release {lock} from redis: {err}");
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let key = format!("{}{key}", self.key_prefix);
+        let value: Option<String> = match self.connection.clone().get(&key).await {
+            Ok(value) => value,
+            Err(err) => {
+                log::warn!("failed to read {key} from redis: {err}");
+                return None;
+            }
+        };
+
+        match serde_json::from_str(&value?) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                log::warn!("ignoring malformed {key} from redis: {err}");
+                None
+            }
+        }
+    }
+
+    /// Reads a value written by [`SharedCache::set_persistent`], its default when missing, or
+    /// None when it can't be read.
+    async fn get_persistent<T: DeserializeOwned + Default>(&self, key: &str) -> Option<T> {
+        let key = format!("{}{key}", self.key_prefix);
+        let value: Option<String> = match self.connection.clone().get(&key).await {
+            Ok(value) => value,
+            Err(err) => {
+                log::warn!("failed to read {key} from redis: {err}");
+                return None;
+            }
+        };
+
+        match value {
+            Some(value) => match serde_json::from_str(&value) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    log::warn!("malformed {key} in redis: {err}");
+                    None
+                }
+            },
+            None => Some(T::default()),
+        }
+    }
+
+    /// Writes the value without expiration, or removes it when None. Returns whether it
+    /// was written.
+    async fn set_persistent<T: Serialize>(&self, key: &str, value: Option<&T>) -> bool {
+        let key = format!("{}{key}", self.key_prefix);
+        let mut connection = self.connection.clone();
+        let result: redis::RedisResult<()> = match value {
+            Some(value) => {
+                let value = serde_json::to_string(value).expect("stored values are serializable");
+                connection.set(&key, value).await
+            }
+            None => connection.del(&key).await,
+        };
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                log::warn!("failed to write {key} to redis: {err}");
+                false
+            }
+        }
+    }
+
+    async fn set<T: Serialize>(&self, key: &str, value: &T, lifespan: u64) {
+        let key = format!("{}{key}", self.key_prefix);
+        let value = serde_json::to_string(value).expect("cached values are serializable");
+
+        let result: redis::RedisResult<()> =
+            self.connection.clone().set_ex(&key, value, lifespan).await;
+        if let Err(err) = result {
+            log::warn!("failed to write {key} to redis: {err}");
+        }
+    }
+}
+
+impl From<&Asset> for StoredAsset {
+    fn from(asset: &Asset) -> Self {
+        Self {
+            size: asset.size,
+            name: asset.name.clone(),
+            version: asset.version.clone(),
+            content_type: asset.content_type.clone(),
+            download_url: asset.download_url.clone(),
+            sha256: asset.sha256.clone(),
+            prerelease: asset.prerelease,
+        }
+    }
+}
+
+impl From<StoredAsset> for Asset {
+    fn from(asset: StoredAsset) -> Self {
+        Self {
+            size: asset.size,
+            name: asset.name,
+            version: asset.version,
+            content_type: asset.content_type,
+            download_url: asset.download_url,
+            sha256: asset.sha256,
+            prerelease: asset.prerelease,
+        }
+    }
+}
+
+impl From<&GameRelease> for StoredGameRelease {
+    fn from(release: &GameRelease) -> Self {
+        Self {
+            assets: (&release.assets).into(),
+            assets_version: release.assets_version.clone(),
+            binaries: to_stored_assets(&release.binaries),
+            changelog: release.changelog.clone(),
+            version: release.version.clone(),
+        }
+    }
+}
+
+impl From<StoredGameRelease> for GameRelease {
+    fn from(release: StoredGameRelease) -> Self {
+        Self {
+            assets: release.assets.into(),
+            assets_version: release.assets_version,
+            binaries: from_stored_assets(release.binaries),
+            changelog: release.changelog,
+            version: release.version,
+        }
+    }
+}
+
+fn to_stored_assets(assets: &Assets) -> HashMap<Platform, StoredAsset> {
+    assets
+        .iter()
+        .map(|(platform, asset)| (*platform, asset.into()))
+        .collect()
+}
+
+fn from_stored_assets(assets: HashMap<Platform, StoredAsset>) -> Assets {
+    assets
+        .into_iter()
+        .map(|(platform, asset)| (platform, asset.into()))
+        .collect()
+}
@@ -0,0 +1,32 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use crate::game_data::DownloadStats;
+
+/// Bounded in-memory history of download statistics snapshots, oldest first, kept when
+/// there is no shared cache to store them in.
+pub struct DownloadStatsHistory {
+    capacity: usize,
+    snapshots: RwLock<VecDeque<Arc<DownloadStats>>>,
+}
+
+impl DownloadStatsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            snapshots: RwLock::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn record(&self, stats: Arc<DownloadStats>) {
+        let mut snapshots = self.snapshots.write().unwrap();
+        while snapshots.len() >= self.capacity.max(1) {
+            snapshots.pop_front();
+        }
+        snapshots.push_back(stats);
+    }
+
+    pub fn snapshots(&self) -> Vec<Arc<DownloadStats>> {
+        self.snapshots.read().unwrap().iter().cloned().collect()
+    }
+}
@@ -0,0 +1,107 @@
+//! Application state seeded with releases, so the routes can be tested without GitHub.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::web;
+use chrono::{TimeZone, Utc};
+use secure_string::SecureString;
+use semver::Version;
+
+use crate::asset_names::DEFAULT_FLAVOR;
+use crate::config::ApiConfig;
+use crate::game_data::{Asset, Assets, GameRelease, Platform, ReleaseNote};
+use crate::{game_release_key, AppData, CachedReleased};
+
+pub const ADMIN_TOKEN: &str = "test_admin_token";
+pub const GAME_VERSION: &str = "0.5.1";
+pub const UPDATER_VERSION: &str = "1.2.0";
+
+/// Platforms of the seeded game release, the updater also has macOS.
+pub const GAME_PLATFORMS: [Platform; 2] = [Platform::WindowsX64, Platform::LinuxX86_64];
+
+pub fn asset(name: &str, version: &str) -> Asset {
+    Asset {
+        size: 1024,
+        name: name.to_string(),
+        version: Version::parse(version).unwrap(),
+        content_type: "application/zip".to_string(),
+        download_url: format!("https://github.invalid/download/{version}/{name}"),
+        sha256: Some(format!("{:064x}", name.len())),
+        prerelease: false,
+    }
+}
+
+pub fn game_release() -> GameRelease {
+    let binaries = GAME_PLATFORMS
+        .into_iter()
+        .map(|platform| (platform, asset(&format!("{platform}.zip"), GAME_VERSION)))
+        .collect();
+
+    GameRelease {
+        assets: asset("assets.zip", GAME_VERSION),
+        assets_version: Version::parse(GAME_VERSION).unwrap(),
+        binaries,
+        changelog: Some("First release".to_string()),
+        version: Version::parse(GAME_VERSION).unwrap(),
+    }
+}
+
+pub fn updater_release() -> Assets {
+    [
+        Platform::WindowsX64,
+        Platform::LinuxX86_64,
+        Platform::MacosArm64,
+    ]
+    .into_iter()
+    .map(|platform| {
+        let name = format!("{platform}_this_updater_of_mine.zip");
+        (platform, asset(&name, UPDATER_VERSION))
+    })
+    .collect::<HashMap<_, _>>()
+}
+
+pub fn release_notes() -> Vec<ReleaseNote> {
+    vec![ReleaseNote {
+        body: Some("First release".to_string()),
+        published_at: Utc.with_ymd_and_hms(2026, 1, 15, 12, 0, 0).unwrap(),
+        title: format!("ThisSpaceOfMine {GAME_VERSION}"),
+        url: format!("https://github.invalid/releases/{GAME_VERSION}"),
+        version: Version::parse(GAME_VERSION).unwrap(),
+    }]
+}
+
+pub fn config() -> ApiConfig {
+    let mut config = ApiConfig::default();
+    config.security.admin_token = Some(SecureString::from(ADMIN_TOKEN));
+    config
+}
+
+/// Builds the state from the configuration with the releases already cached, the jobs
+/// aren't started.
+pub async fn app_data(config: ApiConfig) -> web::Data<AppData> {
+    let app_data = AppData::new(config).await.unwrap();
+    app_data
+        .cache
+        .insert(
+            game_release_key(DEFAULT_FLAVOR),
+            CachedReleased::Game(Arc::new(game_release())),
+        )
+        .await;
+    app_data
+        .cache
+        .insert(
+            "latest_updater_release".to_string(),
+            CachedReleased::Updater(Arc::new(updater_release())),
+        )
+        .await;
+    app_data
+        .cache
+        .insert(
+            "release_notes".to_string(),
+            CachedReleased::ReleaseNotes(Arc::new(release_notes())),
+        )
+        .await;
+
+    web::Data::new(app_data)
+}
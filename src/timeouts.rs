@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::rt::time;
+use actix_web::{web, Error, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::{envelope, AppData};
+
+/// Time given to the handlers of each route group, in seconds, 0 disables the timeout.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RequestTimeouts {
+    /// /game_version and /updater_version.
+    pub version: u64,
+    /// /v2 routes, except the feeds, admin and telemetry ones which keep their group.
+    pub v2: u64,
+    pub feeds: u64,
+    pub admin: u64,
+    pub telemetry: u64,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            version: 20,
+            v2: 20,
+            feeds: 20,
+            // validating the releases goes through every one of them
+            admin: 120,
+            telemetry: 10,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum RouteGroup {
+    Version,
+    V2,
+    Feeds,
+    Admin,
+    Telemetry,
+}
+
+impl RouteGroup {
+    pub(crate) const COUNT: usize = 5;
+
+    pub(crate) fn of(path: &str, tenants: &BTreeMap<String, String>) -> Self {
+        let mut segments = path.split('/').skip(1).peekable();
+        // tenants serve the same routes under their prefix
+        segments.next_if(|segment| tenants.contains_key(*segment));
+
+        // the route groups served under /v2 as well keep their own settings there
+        match (segments.next(), segments.next()) {
+            (Some("feeds"), _) | (Some("v2"), Some("feeds")) => Self::Feeds,
+            (Some("admin"), _) | (Some("v2"), Some("admin")) => Self::Admin,
+            (Some("v1" | "v2"), Some("telemetry")) => Self::Telemetry,
+            (Some("v2"), _) => Self::V2,
+            _ => Self::Version,
+        }
+    }
+}
+
+impl RequestTimeouts {
+    fn seconds(&self, group: RouteGroup) -> u64 {
+        match group {
+            RouteGroup::Version => self.version,
+            RouteGroup::V2 => self.v2,
+            RouteGroup::Feeds => self.feeds,
+            RouteGroup::Admin => self.admin,
+            RouteGroup::Telemetry => self.telemetry,
+        }
+    }
+}
+
+/// Answers with a 504 when a handler takes longer than its route group allows, such as
+/// when GitHub hangs, so stuck requests don't pile up. The handler is dropped, which is
+/// fine for the release caches: another request waiting on the same fetch takes it over.
+pub async fn limit_duration(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let app_data = req
+        .app_data::<web::Data<AppData>>()
+        .cloned()
+        .expect("missing app data");
+
+    let group = RouteGroup::of(req.path(), &app_data.config.tenants);
+    let seconds = app_data.config.request_timeouts.seconds(group);
+    if seconds == 0 {
+        return next.call(req).await;
+    }
+
+    // the request can't be cloned before being routed, so the 504 is prepared beforehand
+    let timeout_response = match group {
+        // the legacy routes have no error body
+        RouteGroup::Version => HttpResponse::GatewayTimeout().finish(),
+        _ => envelope::error(
+            req.request(),
+            StatusCode::GATEWAY_TIMEOUT,
+            "timeout",
+            "the request took too long, retry later",
+            &[],
+        ),
+    };
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    match time::timeout(Duration::from_secs(seconds), next.call(req)).await {
+        Ok(response) => response,
+        Err(_) => {
+            log::warn!("{method} {path} timed out after {seconds}s");
+            Err(InternalError::from_response("request timed out", timeout_response).into())
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! W3C trace context, continued from the `traceparent` header of the incoming requests
+//! and forwarded on the calls made while serving them (GitHub, checksum files, webhooks
+//! and the telemetry sink), so a slow request can be followed end to end.
+
+use std::future::Future;
+
+use actix_web::rt::{spawn, task::JoinHandle};
+
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Trace of the current request, stored in the request extensions by
+/// [`log_requests`](crate::logging::log_requests).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    /// Span of the API within the trace, the parent of the outbound calls.
+    pub span_id: String,
+    flags: u8,
+}
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+impl TraceContext {
+    /// Continues the trace of a valid `traceparent`, or starts a new one.
+    pub fn from_traceparent(traceparent: Option<&str>) -> Self {
+        let (trace_id, flags) = traceparent.and_then(parse).unwrap_or_else(|| {
+            let trace_id = format!("{:032x}", rand::random::<u128>().max(1));
+            (trace_id, 0)
+        });
+
+        Self {
+            trace_id,
+            span_id: format!("{:016x}", rand::random::<u64>().max(1)),
+            flags,
+        }
+    }
+
+    /// Value of the `traceparent` header of the outbound calls.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+
+    /// Trace of the request being served by the current task, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs the future as part of this trace.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+/// Adds the `traceparent` of the request being served, if any, to an outbound request.
+pub fn propagate(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match TraceContext::current() {
+        Some(trace_context) => request.header(TRACEPARENT_HEADER, trace_context.traceparent()),
+        None => request,
+    }
+}
+
+/// Spawns the future as part of the trace of the current task, so the calls it makes
+/// once the request is answered are still traced.
+pub fn spawn_traced<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+{
+    let trace_context = TraceContext::current();
+    spawn(async move {
+        match trace_context {
+            Some(trace_context) => trace_context.scope(future).await,
+            None => future.await,
+        }
+    })
+}
+
+/// Returns the trace id and flags of a `traceparent`, None when it isn't valid.
+fn parse(traceparent: &str) -> Option<(String, u8)> {
+    let mut fields = traceparent.trim().split('-');
+    let version = fields.next()?;
+    let trace_id = fields.next()?;
+    let parent_id = fields.next()?;
+    let flags = fields.next()?;
+
+    // later versions may append fields, the first four keep their meaning
+    if !is_lower_hex(version, 2) || version == "ff" || (version == "00" && fields.next().is_some())
+    {
+        return None;
+    }
+    if !is_lower_hex(trace_id, 32) || !is_lower_hex(parent_id, 16) || !is_lower_hex(flags, 2) {
+        return None;
+    }
+    if trace_id.bytes().all(|b| b == b'0') || parent_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+
+    Some((trace_id.to_string(), u8::from_str_radix(flags, 16).ok()?))
+}
+
+fn is_lower_hex(field: &str, len: usize) -> bool {
+    field.len() == len
+        && field
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    #[test]
+    fn continues_valid_traces() {
+        for (traceparent, flags) in [
+            (format!("00-{TRACE_ID}-00f067aa0ba902b7-01"), "01"),
+            (format!("00-{TRACE_ID}-00f067aa0ba902b7-00"), "00"),
+            (format!(" 00-{TRACE_ID}-00f067aa0ba902b7-01 "), "01"),
+            (format!("cc-{TRACE_ID}-00f067aa0ba902b7-01-future"), "01"),
+        ] {
+            let trace_context = TraceContext::from_traceparent(Some(&traceparent));
+            assert_eq!(trace_context.trace_id, TRACE_ID, "{traceparent}");
+            assert_ne!(trace_context.span_id, "00f067aa0ba902b7");
+            assert_eq!(
+                trace_context.traceparent(),
+                format!("00-{TRACE_ID}-{}-{flags}", trace_context.span_id)
+            );
+        }
+    }
+
+    #[test]
+    fn starts_a_trace_for_invalid_headers() {
+        for traceparent in [
+            String::new(),
+            "garbage".to_string(),
+            format!("00-{TRACE_ID}-00f067aa0ba902b7"),
+            format!("00-{TRACE_ID}-00f067aa0ba902b7-01-extra"),
+            format!("ff-{TRACE_ID}-00f067aa0ba902b7-01"),
+            format!("00-{}-00f067aa0ba902b7-01", TRACE_ID.to_uppercase()),
+            format!("00-{}-00f067aa0ba902b7-01", "0".repeat(32)),
+            format!("00-{TRACE_ID}-0000000000000000-01"),
+            format!("00-{TRACE_ID}-00f067aa0ba902-01"),
+        ] {
+            let trace_context = TraceContext::from_traceparent(Some(&traceparent));
+            assert_ne!(trace_context.trace_id, TRACE_ID, "{traceparent}");
+            assert!(is_lower_hex(&trace_context.trace_id, 32));
+            assert!(trace_context.traceparent().ends_with("-00"));
+        }
+    }
+
+    #[actix_web::test]
+    async fn current_within_scope() {
+        assert_eq!(TraceContext::current(), None);
+
+        let trace_context = TraceContext::from_traceparent(None);
+        let mut spawned = None;
+        trace_context
+            .clone()
+            .scope(async {
+                assert_eq!(TraceContext::current().as_ref(), Some(&trace_context));
+                spawned = Some(spawn_traced(async { TraceContext::current() }));
+            })
+            .await;
+        assert_eq!(TraceContext::current(), None);
+
+        // the spawned task runs once the scope is left
+        assert_eq!(spawned.unwrap().await.unwrap(), Some(trace_context));
+    }
+}
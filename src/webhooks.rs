@@ -0,0 +1,183 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actix_web::rt::time;
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use secure_string::SecureString;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::config::ApiConfig;
+use crate::game_data::GameRelease;
+use crate::reporting::report_error;
+use crate::shared_cache::SharedCache;
+use crate::trace_context::{self, spawn_traced};
+
+const SIGNATURE_HEADER: &str = "X-TSOM-Signature";
+const MAX_ATTEMPTS: u32 = 4;
+const CHANGELOG_SNIPPET_LEN: usize = 1000;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub kind: WebhookKind,
+    /// When set, the body is signed with HMAC-SHA256 in the `X-TSOM-Signature` header.
+    pub secret: Option<SecureString>,
+    /// Reference to the secret read with `security.secret_provider`, instead of `secret`.
+    pub secret_file: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookKind {
+    Discord,
+    #[default]
+    Generic,
+}
+
+/// Announces new game releases to the configured webhooks.
+pub struct ReleaseNotifier {
+    client: reqwest::Client,
+    game_name: String,
+    webhooks: Vec<WebhookConfig>,
+    last_version: Mutex<Option<Version>>,
+}
+
+impl ReleaseNotifier {
+    pub fn from_config(config: &ApiConfig, client: reqwest::Client) -> Self {
+        Self {
+            client,
+            game_name: config.github.game_repository.clone(),
+            webhooks: config.webhooks.clone(),
+            last_version: Mutex::new(None),
+        }
+    }
+
+    /// Fires the webhooks if the release is newer than the last one seen; the first
+    /// release seen after startup is only remembered, so restarts don't announce it again.
+    /// With a shared cache, only the first instance to claim the release announces it.
+    pub async fn on_game_release(&self, release: &GameRelease, shared_cache: Option<&SharedCache>) {
+        let is_new = {
+            let mut last_version = self.last_version.lock().unwrap();
+            let is_new = last_version.as_ref().is_some_and(|v| release.version > *v);
+            if last_version.as_ref().is_none_or(|v| release.version > *v) {
+                *last_version = Some(release.version.clone());
+            }
+            is_new
+        };
+
+        if !is_new {
+            return;
+        }
+
+        if let Some(shared_cache) = shared_cache {
+            if !shared_cache.claim_announcement(&release.version).await {
+                return;
+            }
+        }
+
+        log::info!("announcing {} {}", self.game_name, release.version);
+
+        let mut platforms = release
+            .binaries
+            .keys()
+            .map(|platform| platform.as_str())
+            .collect::<Vec<_>>();
+        platforms.sort_unstable();
+
+        let changelog = release.changelog.as_deref().map(changelog_snippet);
+
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            let payload = match webhook.kind {
+                WebhookKind::Discord => json!({
+                    "content": format!("{} {} is out!", self.game_name, release.version),
+                    "embeds": [{
+                        "title": format!("{} {}", self.game_name, release.version),
+                        "description": changelog,
+                        "fields": [{
+                            "name": "Platforms",
+                            "value": if platforms.is_empty() { "none".to_string() } else { platforms.join(", ") },
+                        }],
+                    }],
+                }),
+                WebhookKind::Generic => json!({
+                    "event": "game_release",
+                    "game": self.game_name,
+                    "version": release.version.to_string(),
+                    "platforms": platforms,
+                    "changelog": changelog,
+                }),
+            };
+
+            spawn_traced(send(
+                self.client.clone(),
+                index,
+                webhook.clone(),
+                payload.to_string(),
+            ));
+        }
+    }
+}
+
+async fn send(client: reqwest::Client, index: usize, webhook: WebhookConfig, body: String) {
+    // Discord webhook urls contain their token, only the host is logged
+    let host = reqwest::Url::parse(&webhook.url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let mut last_error = None;
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            time::sleep(Duration::from_secs(1 << attempt)).await;
+        }
+
+        let mut request = trace_context::propagate(client.post(&webhook.url))
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, body.as_bytes()));
+        }
+
+        let result = request
+            .body(body.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(reqwest::Error::without_url);
+
+        match result {
+            Ok(_) => return,
+            Err(err) => {
+                log::warn!(
+                    "webhooks[{index}] ({host}) failed (attempt {}): {err}",
+                    attempt + 1
+                );
+                last_error = Some(err);
+            }
+        }
+    }
+
+    if let Some(err) = last_error {
+        report_error("failed to deliver a release webhook", &err);
+    }
+}
+
+fn sign(secret: &SecureString, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.unsecure().as_bytes())
+        .expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn changelog_snippet(changelog: &str) -> String {
+    let changelog = changelog.trim();
+    match changelog.char_indices().nth(CHANGELOG_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}…", &changelog[..end]),
+        None => changelog.to_string(),
+    }
+}